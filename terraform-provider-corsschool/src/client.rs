@@ -58,6 +58,19 @@ impl CorsClient {
             .await
             .wrap_err("creating class")
     }
+
+    pub async fn put_class(&self, id: &str, class: &dto::Class) -> Result<dto::Class> {
+        do_request_body(self.client.put(format!("{URL}/classes/{id}")).json(class))
+            .await
+            .wrap_err("updating class")
+    }
+
+    pub async fn delete_class(&self, id: &str) -> Result<()> {
+        do_request(self.client.delete(format!("{URL}/classes/{id}")))
+            .await
+            .wrap_err("deleting class")?;
+        Ok(())
+    }
 }
 
 async fn do_request_body<T: serde::de::DeserializeOwned>(req: RequestBuilder) -> Result<T> {
@@ -5,7 +5,8 @@ use std::collections::HashMap;
 
 use eyre::Context;
 use terustform::{
-    datasource::DataSource, provider::Provider, resource::Resource, DResult, EyreExt, Schema, Value,
+    datasource::DataSource, provider::Provider, resource::Resource, AttrPath, Attribute, DResult,
+    Diagnostics, EyreExt, Mode, Schema, StringValue, Value, ValueModel,
 };
 
 #[tokio::main]
@@ -15,6 +16,12 @@ async fn main() -> eyre::Result<()> {
 
 pub struct ExampleProvider {}
 
+#[derive(terustform::Model)]
+struct ExampleProviderModel {
+    username: StringValue,
+    password: StringValue,
+}
+
 impl Provider for ExampleProvider {
     type Data = client::CorsClient;
     fn name(&self) -> String {
@@ -23,18 +30,52 @@ impl Provider for ExampleProvider {
 
     fn schema(&self) -> Schema {
         Schema {
+            version: 0,
             description: "uwu".to_owned(),
-            attributes: HashMap::new(),
+            attributes: terustform::attrs! {
+                "username" => Attribute::String {
+                    description: "The username, defaults to the `CORSSCHOOL_USERNAME` environment variable".to_owned(),
+                    mode: Mode::Optional,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                },
+                "password" => Attribute::String {
+                    description: "The password, defaults to the `CORSSCHOOL_PASSWORD` environment variable".to_owned(),
+                    mode: Mode::Optional,
+                    sensitive: true,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                },
+            },
+            blocks: HashMap::new(),
+            validators: vec![],
         }
     }
 
-    async fn configure(&self, _config: Value) -> DResult<Self::Data> {
-        let username = std::env::var("CORSSCHOOL_USERNAME")
-            .wrap_err("CORSSCHOOL_USERNAME environment variable not set")
-            .eyre_to_tf()?;
-        let password = std::env::var("CORSSCHOOL_PASSWORD")
-            .wrap_err("CORSSCHOOL_PASSWORD environment variable not set")
-            .eyre_to_tf()?;
+    async fn configure(&self, config: Value, _diags: &mut Diagnostics) -> DResult<Self::Data> {
+        let config = ExampleProviderModel::from_root_value(config)?;
+
+        let username = match config
+            .username
+            .expect_known_or_null(AttrPath::attr("username"))?
+        {
+            Some(username) => username.clone(),
+            None => std::env::var("CORSSCHOOL_USERNAME")
+                .wrap_err("CORSSCHOOL_USERNAME environment variable not set")
+                .eyre_to_tf()?,
+        };
+        let password = match config
+            .password
+            .expect_known_or_null(AttrPath::attr("password"))?
+        {
+            Some(password) => password.clone(),
+            None => std::env::var("CORSSCHOOL_PASSWORD")
+                .wrap_err("CORSSCHOOL_PASSWORD environment variable not set")
+                .eyre_to_tf()?,
+        };
         let client = client::CorsClient::new(username, password)
             .await
             .wrap_err("failed to create client")
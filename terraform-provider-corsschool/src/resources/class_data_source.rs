@@ -1,9 +1,7 @@
-use std::collections::HashMap;
-
 use eyre::Context;
 use terustform::{
-    datasource::DataSource, AttrPath, Attribute, DResult, EyreExt, Mode, Schema, StringValue,
-    Value, ValueModel,
+    datasource::TypedDataSource, AttrPath, DResult, Diagnostics, EyreExt, Schema, SchemaModel,
+    StringValue, Validator, Value,
 };
 
 use crate::client::CorsClient;
@@ -12,20 +10,33 @@ pub struct ClassDataSource {
     client: CorsClient,
 }
 
-#[derive(terustform::Model)]
-pub(super) struct ClassModel {
-    pub(super) id: StringValue,
-    pub(super) name: StringValue,
-    pub(super) description: StringValue,
-    pub(super) discord_id: StringValue,
+/// Get a class by name
+#[derive(terustform::Model, terustform::Schema)]
+pub struct ClassModel {
+    /// The UUID
+    #[terustform(required, validator = Validator::uuid())]
+    id: StringValue,
+    /// The description
+    #[terustform(computed)]
+    name: StringValue,
+    /// The description
+    #[terustform(computed)]
+    description: StringValue,
+    /// The discord ID of the class
+    #[terustform(computed)]
+    discord_id: StringValue,
 }
 
-impl DataSource for ClassDataSource {
+impl TypedDataSource for ClassDataSource {
     type ProviderData = CorsClient;
+    type Model = ClassModel;
 
-    async fn read(&self, config: Value) -> DResult<Value> {
-        let model = ClassModel::from_value(config, &AttrPath::root())?;
-
+    async fn read(
+        &self,
+        model: ClassModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<ClassModel> {
         let class = self
             .client
             .get_class(model.id.expect_known(AttrPath::attr("id"))?)
@@ -38,8 +49,7 @@ impl DataSource for ClassDataSource {
             name: class.name.into(),
             description: class.description.into(),
             discord_id: class.discord_id.into(),
-        }
-        .to_value())
+        })
     }
 
     fn name(provider_name: &str) -> String {
@@ -47,44 +57,7 @@ impl DataSource for ClassDataSource {
     }
 
     fn schema() -> Schema {
-        Schema {
-            description: "Get a class by name".to_owned(),
-            attributes: HashMap::from([
-                (
-                    "id".to_owned(),
-                    // TODO: UUID validation :3
-                    Attribute::String {
-                        description: "The UUID".to_owned(),
-                        mode: Mode::Required,
-                        sensitive: false,
-                    },
-                ),
-                (
-                    "name".to_owned(),
-                    Attribute::String {
-                        description: "The description".to_owned(),
-                        mode: Mode::Computed,
-                        sensitive: false,
-                    },
-                ),
-                (
-                    "description".to_owned(),
-                    Attribute::String {
-                        description: "The description".to_owned(),
-                        mode: Mode::Computed,
-                        sensitive: false,
-                    },
-                ),
-                (
-                    "discord_id".to_owned(),
-                    Attribute::String {
-                        description: "The discord ID of the class".to_owned(),
-                        mode: Mode::Computed,
-                        sensitive: false,
-                    },
-                ),
-            ]),
-        }
+        ClassModel::schema()
     }
 
     fn new(data: Self::ProviderData) -> DResult<Self> {
@@ -1,104 +1,152 @@
 use terustform::{
-    resource::Resource, AttrPath, Attribute, DResult, EyreExt, Mode, Schema, Value, ValueModel,
+    resource::TypedResource, AttrPath, DResult, Diagnostics, EyreExt, PlanModifier, Schema,
+    SchemaModel, StringValue, Value,
 };
 
 use crate::client::CorsClient;
 
-use super::class_data_source::ClassModel;
-
 pub struct ClassResource {
     client: CorsClient,
 }
 
-impl Resource for ClassResource {
-    type ProviderData = CorsClient;
+/// A class
+#[derive(terustform::Model, terustform::Schema)]
+#[terustform(version = 1)]
+pub struct ClassResourceModel {
+    /// The UUID
+    #[terustform(
+        computed,
+        plan_modifier = PlanModifier::use_state_for_unknown(),
+        plan_modifier = PlanModifier::not_null()
+    )]
+    id: StringValue,
+    /// The description
+    #[terustform(required)]
+    name: StringValue,
+    /// The description
+    #[terustform(required)]
+    description: StringValue,
+    /// The discord ID of the class
+    #[terustform(optional)]
+    discord_id: StringValue,
+}
+
+impl ClassResourceModel {
+    /// The class as sent to the API when creating or updating it.
+    fn to_dto(&self) -> DResult<dto::Class> {
+        Ok(dto::Class {
+            id: Default::default(),
+            members: vec![],
+            name: self.name.expect_known(AttrPath::attr("name"))?.clone(),
+            description: self
+                .description
+                .expect_known(AttrPath::attr("description"))?
+                .clone(),
+            discord_id: self
+                .discord_id
+                .expect_known_or_null(AttrPath::attr("discord_id"))?
+                .cloned(),
+        })
+    }
+}
 
-    async fn read(&self, current_state: Value) -> DResult<Value> {
-        let model = ClassModel::from_value(current_state, &AttrPath::root())?;
+impl TypedResource for ClassResource {
+    type ProviderData = CorsClient;
+    type Model = ClassResourceModel;
 
+    async fn read(
+        &self,
+        model: ClassResourceModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<Option<ClassResourceModel>> {
         let class = self
             .client
             .get_class(model.id.expect_known(AttrPath::attr("id"))?)
             .await
             .eyre_to_tf()?;
 
-        Ok(ClassModel {
+        Ok(Some(ClassResourceModel {
             id: model.id,
             name: class.name.into(),
             description: class.description.into(),
             discord_id: class.discord_id.into(),
-        }
-        .to_value())
+        }))
     }
 
-    async fn create(&self, _config: Value, plan: Value) -> DResult<Value> {
-        let model = ClassModel::from_root_value(plan)?;
-
+    async fn create(
+        &self,
+        _config: ClassResourceModel,
+        model: ClassResourceModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<ClassResourceModel> {
         let class = self
             .client
-            .post_class(&dto::Class {
-                id: Default::default(),
-                members: vec![],
-                name: model.name.expect_known(AttrPath::attr("name"))?.clone(),
-                description: model
-                    .description
-                    .expect_known(AttrPath::attr("description"))?
-                    .clone(),
-                discord_id: model
-                    .discord_id
-                    .expect_known_or_null(AttrPath::attr("discord_id"))?
-                    .cloned(),
-            })
+            .post_class(&model.to_dto()?)
             .await
             .eyre_to_tf()?;
 
-        Ok(ClassModel {
+        Ok(ClassResourceModel {
             id: class.id.to_string().into(),
             name: class.name.into(),
             description: class.description.into(),
             discord_id: class.discord_id.into(),
-        }
-        .to_value())
+        })
     }
 
-    async fn update(&self, _config: Value, _plan: Value, _state: Value) -> DResult<Value> {
-        todo!()
+    async fn update(
+        &self,
+        _config: ClassResourceModel,
+        plan: ClassResourceModel,
+        state: ClassResourceModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<ClassResourceModel> {
+        let class = self
+            .client
+            .put_class(
+                state.id.expect_known(AttrPath::attr("id"))?,
+                &plan.to_dto()?,
+            )
+            .await
+            .eyre_to_tf()?;
+
+        Ok(ClassResourceModel {
+            id: state.id,
+            name: class.name.into(),
+            description: class.description.into(),
+            discord_id: class.discord_id.into(),
+        })
+    }
+
+    async fn delete(
+        &self,
+        state: ClassResourceModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<()> {
+        self.client
+            .delete_class(state.id.expect_known(AttrPath::attr("id"))?)
+            .await
+            .eyre_to_tf()
     }
 
-    async fn delete(&self, _state: Value) -> DResult<Value> {
-        todo!()
+    async fn import(&self, id: String, _diags: &mut Diagnostics) -> DResult<ClassResourceModel> {
+        Ok(ClassResourceModel {
+            id: id.into(),
+            name: StringValue::Null,
+            description: StringValue::Null,
+            discord_id: StringValue::Null,
+        })
     }
 
     fn name(provider_name: &str) -> String {
         format!("{provider_name}_class")
     }
 
-    fn schema() -> terustform::Schema {
-        Schema {
-            description: "A class".into(),
-            attributes: terustform::attrs! {
-                "id" => Attribute::String {
-                    description: "The UUID".to_owned(),
-                    mode: Mode::Computed,
-                    sensitive: false,
-                },
-                "name" => Attribute::String {
-                    description: "The description".to_owned(),
-                    mode: Mode::Required,
-                    sensitive: false,
-                },
-                "description" => Attribute::String {
-                    description: "The description".to_owned(),
-                    mode: Mode::Required,
-                    sensitive: false,
-                },
-                "discord_id" => Attribute::String {
-                    description: "The discord ID of the class".to_owned(),
-                    mode: Mode::Optional,
-                    sensitive: false,
-                },
-            },
-        }
+    fn schema() -> Schema {
+        ClassResourceModel::schema()
     }
 
     fn new(client: Self::ProviderData) -> DResult<Self> {
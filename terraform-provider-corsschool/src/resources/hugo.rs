@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use eyre::Context;
 use terustform::{
-    datasource::DataSource, Attribute, DResult, EyreExt, Mode, Schema, StringValue, Value,
-    ValueModel,
+    datasource::DataSource, Attribute, DResult, Diagnostics, EyreExt, Mode, Schema, StringValue,
+    Value, ValueModel,
 };
 
 use crate::client::CorsClient;
@@ -18,7 +20,12 @@ struct HugoDataSourceModel {
 impl DataSource for HugoDataSource {
     type ProviderData = CorsClient;
 
-    async fn read(&self, _config: Value) -> DResult<Value> {
+    async fn read(
+        &self,
+        _config: Value,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<Value> {
         let hugo = self
             .client
             .get_hugo()
@@ -38,14 +45,20 @@ impl DataSource for HugoDataSource {
 
     fn schema() -> Schema {
         Schema {
+            version: 0,
             description: "Get Hugo Boss".to_owned(),
             attributes: terustform::attrs! {
                 "hugo" => Attribute::String {
                     description: "Hugo Boss".to_owned(),
                     mode: Mode::Computed,
                     sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
                 },
             },
+            blocks: HashMap::new(),
+            validators: vec![],
         }
     }
 
@@ -1,74 +1,58 @@
 use terustform::{
-    datasource::DataSource, AttrPath, Attribute, DResult, Mode, Schema, StringValue, Value,
-    ValueModel,
+    datasource::TypedDataSource, AttrPath, DResult, Diagnostics, Schema, SchemaModel, StringValue,
+    Value,
 };
 
 use crate::client::CorsClient;
 
 pub struct ExampleDataSource {}
 
-#[derive(terustform::Model)]
-struct ExampleDataSourceModel {
+/// an example
+#[derive(terustform::Model, terustform::Schema)]
+pub struct ExampleDataSourceModel {
+    /// a cool name
+    #[terustform(required)]
     name: StringValue,
+    /// the meow of the cat
+    #[terustform(computed)]
     meow: StringValue,
+    /// the ID of the meowy cat
+    #[terustform(required)]
     paws: ExampleDataSourceModelPaws,
 }
 
-#[derive(terustform::Model)]
+#[derive(terustform::Model, terustform::Schema)]
 struct ExampleDataSourceModelPaws {
+    /// meow
+    #[terustform(required)]
     left: StringValue,
+    /// meow
+    #[terustform(optional)]
     right: StringValue,
 }
 
-impl DataSource for ExampleDataSource {
+impl TypedDataSource for ExampleDataSource {
     type ProviderData = CorsClient;
+    type Model = ExampleDataSourceModel;
 
     fn name(provider_name: &str) -> String {
         format!("{provider_name}_kitty")
     }
 
     fn schema() -> Schema {
-        Schema {
-            description: "an example".to_owned(),
-            attributes: terustform::attrs! {
-                "name" => Attribute::String {
-                    description: "a cool name".to_owned(),
-                    mode: Mode::Required,
-                    sensitive: false,
-                },
-                "meow" => Attribute::String {
-                    description: "the meow of the cat".to_owned(),
-                    mode: Mode::Computed,
-                    sensitive: false,
-                },
-                "paws" => Attribute::Object {
-                    description: "the ID of the meowy cat".to_owned(),
-                    mode: Mode::Required,
-                    sensitive: false,
-                    attrs: terustform::attrs! {
-                        "left" => Attribute::String {
-                            description: "meow".to_owned(),
-                            mode: Mode::Required,
-                            sensitive: false,
-                        },
-                        "right" => Attribute::String {
-                            description: "meow".to_owned(),
-                            mode: Mode::Optional,
-                            sensitive: false,
-                        },
-                    },
-                },
-            },
-        }
+        ExampleDataSourceModel::schema()
     }
 
     fn new(_data: Self::ProviderData) -> DResult<Self> {
         Ok(ExampleDataSource {})
     }
 
-    async fn read(&self, config: Value) -> DResult<Value> {
-        let mut model = ExampleDataSourceModel::from_value(config, &AttrPath::root())?;
-
+    async fn read(
+        &self,
+        mut model: ExampleDataSourceModel,
+        _provider_meta: Value,
+        _diags: &mut Diagnostics,
+    ) -> DResult<ExampleDataSourceModel> {
         let name_str = model.name.expect_known(AttrPath::attr("name"))?;
 
         let meow = format!("mrrrrr i am {name_str}");
@@ -76,6 +60,6 @@ impl DataSource for ExampleDataSource {
         model.meow = StringValue::Known(meow);
         model.paws.right = StringValue::Known("O".to_owned());
 
-        Ok(model.to_value())
+        Ok(model)
     }
 }
@@ -0,0 +1,193 @@
+//! Parsing of the `#[terustform(...)]` helper attributes.
+
+use syn::{ext::IdentExt, spanned::Spanned};
+
+/// The `#[terustform(...)]` attributes on a struct.
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    pub(crate) description: Option<String>,
+    pub(crate) version: Option<syn::LitInt>,
+}
+
+/// The `#[terustform(...)]` attributes on a field.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub(crate) rename: Option<String>,
+    pub(crate) skip: bool,
+    /// `Some(None)` for `default`, which uses `Default::default()`,
+    /// `Some(Some(path))` for `default = path`, which calls the function at `path`.
+    pub(crate) default: Option<Option<syn::Path>>,
+    pub(crate) flatten: bool,
+    pub(crate) description: Option<String>,
+    pub(crate) required: bool,
+    pub(crate) optional: bool,
+    pub(crate) computed: bool,
+    pub(crate) sensitive: bool,
+    pub(crate) validators: Vec<syn::Expr>,
+    pub(crate) plan_modifiers: Vec<syn::Expr>,
+}
+
+/// The `#[terustform(...)]` attributes on an enum variant.
+#[derive(Default)]
+pub(crate) struct VariantAttrs {
+    pub(crate) rename: Option<String>,
+}
+
+impl ContainerAttrs {
+    pub(crate) fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut this = Self {
+            description: doc_comment(attrs),
+            ..Self::default()
+        };
+
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("terustform"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("description") {
+                    this.description = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else if meta.path.is_ident("version") {
+                    this.version = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown terustform attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(this)
+    }
+}
+
+impl FieldAttrs {
+    pub(crate) fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut this = Self {
+            description: doc_comment(&field.attrs),
+            ..Self::default()
+        };
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("terustform"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    this.rename = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    this.skip = true;
+                } else if meta.path.is_ident("default") {
+                    this.default = Some(if meta.input.peek(syn::Token![=]) {
+                        Some(meta.value()?.parse()?)
+                    } else {
+                        None
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    this.flatten = true;
+                } else if meta.path.is_ident("description") {
+                    this.description = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else if meta.path.is_ident("required") {
+                    this.required = true;
+                } else if meta.path.is_ident("optional") {
+                    this.optional = true;
+                } else if meta.path.is_ident("computed") {
+                    this.computed = true;
+                } else if meta.path.is_ident("sensitive") {
+                    this.sensitive = true;
+                } else if meta.path.is_ident("validator") {
+                    this.validators.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("plan_modifier") {
+                    this.plan_modifiers.push(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown terustform attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if this.skip && this.flatten {
+            return Err(syn::Error::new(
+                field.span(),
+                "fields cannot be both skipped and flattened",
+            ));
+        }
+        if this.flatten && this.rename.is_some() {
+            return Err(syn::Error::new(
+                field.span(),
+                "flattened fields cannot be renamed",
+            ));
+        }
+        if this.required && (this.optional || this.computed) {
+            return Err(syn::Error::new(
+                field.span(),
+                "required attributes cannot also be optional or computed",
+            ));
+        }
+
+        Ok(this)
+    }
+
+    /// The name of the attribute for the field `ident`.
+    pub(crate) fn name(&self, ident: &syn::Ident) -> String {
+        self.rename
+            .clone()
+            .unwrap_or_else(|| ident.unraw().to_string())
+    }
+}
+
+impl VariantAttrs {
+    pub(crate) fn parse(variant: &syn::Variant) -> syn::Result<Self> {
+        let mut this = Self::default();
+
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("terustform"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    this.rename = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else {
+                    return Err(meta.error("unknown terustform attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(this)
+    }
+
+    /// The string value for the variant `ident`.
+    pub(crate) fn name(&self, ident: &syn::Ident) -> String {
+        self.rename
+            .clone()
+            .unwrap_or_else(|| ident.unraw().to_string())
+    }
+}
+
+/// Joins the `///` doc comment lines, which are desugared to `#[doc = "..."]`.
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n").trim().to_owned())
+    }
+}
@@ -0,0 +1,105 @@
+//! Fieldless enums are modelled as strings, one per variant.
+
+use quote::quote;
+use syn::spanned::Spanned;
+
+use crate::attrs::VariantAttrs;
+
+pub(crate) fn enum_model_inner(
+    enum_name: syn::Ident,
+    generics: syn::Generics,
+    data: syn::DataEnum,
+) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let tf = quote!(::terustform::__derive_private);
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new(
+            enum_name.span(),
+            "enum models must have at least one variant",
+        ));
+    }
+
+    let variants = data
+        .variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, syn::Fields::Unit) {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "enum models must only have variants without fields",
+                ));
+            }
+            let attrs = VariantAttrs::parse(variant)?;
+            Ok((&variant.ident, attrs.name(&variant.ident)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let expected = variants
+        .iter()
+        .map(|(_, name)| format!("'{name}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let from_arms = variants
+        .iter()
+        .map(|(ident, name)| quote! { #name => return #tf::Ok(Self::#ident), });
+    let to_arms = variants
+        .iter()
+        .map(|(ident, name)| quote! { Self::#ident => #name, });
+    let names = variants.iter().map(|(_, name)| name);
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #tf::ValueModel
+            for #enum_name #type_generics #where_clause
+        {
+            fn from_value(v: #tf::Value, path: &#tf::AttrPath) -> #tf::DResult<Self> {
+                let msg = match v {
+                    #tf::BaseValue::Known(#tf::ValueKind::String(s)) => match s.as_str() {
+                        #(#from_arms)*
+                        _ => format!("Expected one of {}, found '{}'", #expected, s),
+                    },
+                    #tf::BaseValue::Unknown(_) => #tf::ToOwned::to_owned("Expected string, found unknown value"),
+                    #tf::BaseValue::Null => #tf::ToOwned::to_owned("Expected string, found null value"),
+                    #tf::BaseValue::Known(v) => format!("Expected string, found {} value", v.diagnostic_type_str()),
+                };
+                #tf::Err(#tf::Diagnostics::from(#tf::Diagnostic::with_path(
+                    #tf::Diagnostic::error_string(msg),
+                    #tf::Clone::clone(path),
+                )))
+            }
+
+            fn to_value(self) -> #tf::Value {
+                let s = match self {
+                    #(#to_arms)*
+                };
+                #tf::BaseValue::Known(#tf::ValueKind::String(#tf::ToOwned::to_owned(s)))
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #tf::AttributeModel
+            for #enum_name #type_generics #where_clause
+        {
+            fn attribute(
+                description: #tf::String,
+                mode: #tf::Mode,
+                sensitive: bool,
+                mut validators: #tf::Vec<#tf::Validator>,
+                plan_modifiers: #tf::Vec<#tf::PlanModifier>,
+            ) -> #tf::Attribute {
+                validators.push(#tf::Validator::one_of([#(#names),*]));
+                #tf::Attribute::String {
+                    description,
+                    mode,
+                    sensitive,
+                    validators,
+                    plan_modifiers,
+                    default: #tf::None,
+                }
+            }
+        }
+    })
+}
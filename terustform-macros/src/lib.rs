@@ -1,9 +1,15 @@
 use quote::quote;
-use syn::spanned::Spanned;
+use syn::{ext::IdentExt, spanned::Spanned};
 
-// This macro should only reference items in `terustform::__derive_private`.
+mod attrs;
+mod enum_model;
+mod schema;
 
-#[proc_macro_derive(Model)]
+use attrs::FieldAttrs;
+
+// These macros should only reference items in `terustform::__derive_private`.
+
+#[proc_macro_derive(Model, attributes(terustform))]
 pub fn data_source_model(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
     match data_source_model_inner(input) {
@@ -12,16 +18,33 @@ pub fn data_source_model(input: proc_macro::TokenStream) -> proc_macro::TokenStr
     }
 }
 
+/// Derives `SchemaModel` and `AttributeModel`, building the schema from the fields and their
+/// `#[terustform(...)]` attributes. Doc comments are used as descriptions.
+#[proc_macro_derive(Schema, attributes(terustform))]
+pub fn schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match schema::schema_inner(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
 fn data_source_model_inner(
     input: syn::DeriveInput,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
     let struct_name = input.ident;
 
-    let syn::Data::Struct(data) = input.data else {
-        return Err(syn::Error::new(
-            struct_name.span(),
-            "models must be structs",
-        ));
+    let data = match input.data {
+        syn::Data::Struct(data) => data,
+        syn::Data::Enum(data) => {
+            return enum_model::enum_model_inner(struct_name, input.generics, data);
+        }
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new(
+                struct_name.span(),
+                "models must be structs or enums",
+            ));
+        }
     };
     let syn::Fields::Named(fields) = data.fields else {
         return Err(syn::Error::new(
@@ -36,48 +59,128 @@ fn data_source_model_inner(
         .named
         .into_iter()
         .map(|field| {
+            let attrs = FieldAttrs::parse(&field)?;
             let Some(name) = field.ident else {
                 return Err(syn::Error::new(field.span(), "field must be named"));
             };
 
-            Ok((name, field.ty))
+            Ok((name, field.ty, attrs))
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let field_extractions = fields.iter().map(|(name, ty)| {
-        let name_str = proc_macro2::Literal::string(&name.to_string());
-        quote! {
-            let #tf::Some(#name) = obj.remove(#name_str) else {
-                return #tf::Err(
-                    #tf::Diagnostics::from(#tf::Diagnostic::error_string(
+    // Fields are bound to prefixed locals so they can't shadow `obj` or `path`.
+    let locals = fields
+        .iter()
+        .map(|(name, _, _)| quote::format_ident!("__field_{}", name.unraw()))
+        .collect::<Vec<_>>();
+
+    // Every field is decoded even if an earlier one failed, so that all errors are reported at once.
+    let field_extractions = fields.iter().zip(&locals).map(|((name, ty, attrs), local)| {
+        if attrs.skip {
+            return quote! {
+                let #local = #tf::Some(<#ty as #tf::Default>::default());
+            };
+        }
+        if attrs.flatten {
+            return quote! {
+                let #local = match <#ty as #tf::ObjectModel>::from_object(obj, path) {
+                    #tf::Ok(v) => #tf::Some(v),
+                    #tf::Err(e) => {
+                        __errors.extend(e);
+                        #tf::None
+                    }
+                };
+            };
+        }
+
+        let name_str = proc_macro2::Literal::string(&attrs.name(name));
+        let missing = match &attrs.default {
+            None => quote! {
+                {
+                    __errors.push(#tf::Diagnostic::error_string(
                         format!("Expected property '{}' when deserializing value, which was not present in the value", #name_str),
-                    ).with_path(path.clone()))
-                );
+                    ).with_path(path.append_attribute_name(#tf::ToOwned::to_owned(#name_str))));
+                    #tf::None
+                }
+            },
+            Some(None) => quote! { #tf::Some(<#ty as #tf::Default>::default()) },
+            Some(Some(default)) => quote! { #tf::Some(#default()) },
+        };
+        quote! {
+            let #local = match obj.remove(#name_str) {
+                #tf::Some(v) => match <#ty as #tf::ValueModel>::from_value(
+                    v,
+                    &path.append_attribute_name(#tf::ToOwned::to_owned(#name_str))
+                ) {
+                    #tf::Ok(v) => #tf::Some(v),
+                    #tf::Err(e) => {
+                        __errors.extend(e);
+                        #tf::None
+                    }
+                },
+                #tf::None => #missing,
             };
-            let #name = <#ty as #tf::ValueModel>::from_value(
-                #name,
-                &path.append_attribute_name(#tf::ToOwned::to_owned(#name_str))
-            )?;
         }
     });
-    let constructor_fields = fields.iter().map(|(name, _)| quote! { #name, });
+    let constructor_fields = fields
+        .iter()
+        .zip(&locals)
+        .map(|((name, _, _), local)| quote! { #name: #local, });
 
-    let to_value_fields = fields.iter().map(|(name, ty)| {
-        let name_str = proc_macro2::Literal::string(&name.to_string());
+    let to_object_fields = fields.iter().map(|(name, ty, attrs)| {
+        if attrs.skip {
+            return quote! {};
+        }
+        if attrs.flatten {
+            return quote! {
+                <#ty as #tf::ObjectModel>::to_object(self.#name, obj);
+            };
+        }
 
-        quote! { (#name_str, <#ty as #tf::ValueModel>::to_value(self.#name)), }
+        let name_str = proc_macro2::Literal::string(&attrs.name(name));
+        quote! {
+            obj.insert(
+                #tf::ToOwned::to_owned(#name_str),
+                <#ty as #tf::ValueModel>::to_value(self.#name),
+            );
+        }
     });
 
     let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
 
     Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #tf::ObjectModel
+            for #struct_name #type_generics #where_clause
+        {
+            fn from_object(obj: &mut #tf::BTreeMap<#tf::String, #tf::Value>, path: &#tf::AttrPath) -> #tf::DResult<Self> {
+                let mut __errors = #tf::Diagnostics::default();
+
+                #(#field_extractions)*
+
+                #(
+                    let #tf::Some(#locals) = #locals else {
+                        return #tf::Err(__errors);
+                    };
+                )*
+
+                #tf::Ok(#struct_name {
+                    #(#constructor_fields)*
+                })
+            }
+
+            fn to_object(self, obj: &mut #tf::BTreeMap<#tf::String, #tf::Value>) {
+                #(#to_object_fields)*
+            }
+        }
+
         #[automatically_derived]
         impl #impl_generics #tf::ValueModel
             for #struct_name #type_generics #where_clause
         {
             fn from_value(v: #tf::Value, path: &#tf::AttrPath) -> #tf::DResult<Self> {
                 match v {
-                    #tf::BaseValue::Unknown => {
+                    #tf::BaseValue::Unknown(_) => {
                         return #tf::Err(#tf::Diagnostics::from(#tf::Diagnostic::with_path(
                             #tf::Diagnostic::error_string(#tf::ToOwned::to_owned("Expected object, found unknown value")),
                             #tf::Clone::clone(&path),
@@ -90,11 +193,20 @@ fn data_source_model_inner(
                         )));
                     },
                     #tf::BaseValue::Known(#tf::ValueKind::Object(mut obj)) => {
-                        #(#field_extractions)*
+                        let result = <Self as #tf::ObjectModel>::from_object(&mut obj, path);
 
-                        Ok(#struct_name {
-                            #(#constructor_fields)*
-                        })
+                        // Whatever is left over wasn't taken by any field.
+                        let mut errors = match result {
+                            #tf::Ok(_) if obj.is_empty() => return result,
+                            #tf::Ok(_) => #tf::Diagnostics::default(),
+                            #tf::Err(errors) => errors,
+                        };
+                        for name in obj.into_keys() {
+                            errors.push(#tf::Diagnostic::error_string(
+                                format!("Unexpected property '{}' when deserializing value", name),
+                            ).with_path(path.append_attribute_name(name)));
+                        }
+                        #tf::Err(errors)
                     },
                     #tf::BaseValue::Known(v) => {
                         return #tf::Err(#tf::Diagnostics::from(#tf::Diagnostic::with_path(
@@ -106,11 +218,9 @@ fn data_source_model_inner(
             }
 
             fn to_value(self) -> #tf::Value {
-                #tf::new_object(
-                    [
-                        #(#to_value_fields)*
-                    ]
-                )
+                let mut obj = #tf::BTreeMap::new();
+                <Self as #tf::ObjectModel>::to_object(self, &mut obj);
+                #tf::BaseValue::Known(#tf::ValueKind::Object(obj))
             }
         }
     })
@@ -0,0 +1,126 @@
+use quote::quote;
+use syn::spanned::Spanned;
+
+use crate::attrs::{ContainerAttrs, FieldAttrs};
+
+pub(crate) fn schema_inner(
+    input: syn::DeriveInput,
+) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let struct_name = input.ident;
+    let container = ContainerAttrs::parse(&input.attrs)?;
+
+    let syn::Data::Struct(data) = input.data else {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "schemas must be structs",
+        ));
+    };
+    let syn::Fields::Named(fields) = data.fields else {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "schemas must have named fields",
+        ));
+    };
+
+    let tf = quote!(::terustform::__derive_private);
+
+    let attributes = fields
+        .named
+        .iter()
+        .map(|field| {
+            let Some(name) = &field.ident else {
+                return Err(syn::Error::new(field.span(), "field must be named"));
+            };
+            let attrs = FieldAttrs::parse(field)?;
+            let ty = &field.ty;
+
+            if attrs.skip {
+                return Ok(quote! {});
+            }
+            if attrs.flatten {
+                return Ok(quote! {
+                    #tf::Extend::extend(
+                        &mut attributes,
+                        <#ty as #tf::SchemaModel>::schema().attributes,
+                    );
+                });
+            }
+
+            let name_str = proc_macro2::Literal::string(&attrs.name(name));
+            let description = attrs.description.unwrap_or_default();
+            let mode = match (attrs.required, attrs.optional, attrs.computed) {
+                (true, _, _) => quote!(Required),
+                (false, true, true) => quote!(OptionalComputed),
+                (false, false, true) => quote!(Computed),
+                (false, _, false) => quote!(Optional),
+            };
+            let sensitive = attrs.sensitive;
+            let validators = attrs.validators;
+            let plan_modifiers = attrs.plan_modifiers;
+
+            // Going through the trait makes the compiler check that the field type matches the attribute.
+            Ok(quote! {
+                attributes.insert(
+                    #tf::ToOwned::to_owned(#name_str),
+                    <#ty as #tf::AttributeModel>::attribute(
+                        #tf::ToOwned::to_owned(#description),
+                        #tf::Mode::#mode,
+                        #sensitive,
+                        #tf::Vec::from([#(#validators),*]),
+                        #tf::Vec::from([#(#plan_modifiers),*]),
+                    ),
+                );
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let description = container.description.unwrap_or_default();
+    let version = container
+        .version
+        .map(|v| quote!(#v))
+        .unwrap_or_else(|| quote!(0));
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #tf::SchemaModel
+            for #struct_name #type_generics #where_clause
+        {
+            fn schema() -> #tf::Schema {
+                let mut attributes = #tf::HashMap::new();
+                #(#attributes)*
+
+                #tf::Schema {
+                    version: #version,
+                    description: #tf::ToOwned::to_owned(#description),
+                    attributes,
+                    ..#tf::Default::default()
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #tf::AttributeModel
+            for #struct_name #type_generics #where_clause
+        {
+            fn attribute(
+                description: #tf::String,
+                mode: #tf::Mode,
+                sensitive: bool,
+                validators: #tf::Vec<#tf::Validator>,
+                plan_modifiers: #tf::Vec<#tf::PlanModifier>,
+            ) -> #tf::Attribute {
+                #tf::Attribute::Object {
+                    description,
+                    mode,
+                    sensitive,
+                    validators,
+                    plan_modifiers,
+                    default: #tf::None,
+                    attrs: <Self as #tf::SchemaModel>::schema().attributes,
+                }
+            }
+        }
+    })
+}
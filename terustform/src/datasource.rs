@@ -3,7 +3,7 @@ use std::future::Future;
 use crate::{
     provider::{BoxFut, MkDataSource, ProviderData},
     values::Value,
-    Schema,
+    Diagnostics, Schema, ValueModel,
 };
 
 use super::DResult;
@@ -12,10 +12,24 @@ pub trait DataSource: Sized + Send + Sync + 'static {
     type ProviderData: ProviderData;
 
     // todo: probably want some kind of Value+Schema thing like tfsdk? whatever.
-    fn read(&self, config: Value) -> impl Future<Output = DResult<Value>> + Send + Sync;
+    // `provider_meta` is the value of the `provider_meta` block, null if there is none.
+    // Warnings can be pushed to `diags`, they are reported whether the method succeeds or not.
+    fn read(
+        &self,
+        config: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync;
 
     fn name(provider_name: &str) -> String;
     fn schema() -> Schema;
+    /// Validates the configuration of the data source beyond what the validators in the schema do.
+    /// This may be called before the provider is configured, so it cannot access the data source.
+    /// The configuration may contain unknown values.
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        let _ = (config, diags);
+        Ok(())
+    }
     fn new(data: Self::ProviderData) -> DResult<Self>;
 
     fn erase() -> MkDataSource<Self::ProviderData> {
@@ -23,12 +37,79 @@ pub trait DataSource: Sized + Send + Sync + 'static {
     }
 }
 
+/// A [`DataSource`] that works with a typed model instead of raw values.
+/// The framework decodes the config into the model and encodes the returned model,
+/// reporting decoding errors with the path of the offending attribute.
+///
+/// Types implementing this trait implement [`DataSource`] automatically.
+pub trait TypedDataSource: Sized + Send + Sync + 'static {
+    type ProviderData: ProviderData;
+    /// The model of the config and state, matching [`TypedDataSource::schema`].
+    type Model: ValueModel + Send + Sync;
+
+    fn read(
+        &self,
+        config: Self::Model,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Self::Model>> + Send + Sync;
+
+    fn name(provider_name: &str) -> String;
+    fn schema() -> Schema;
+    /// See [`DataSource::validate_config`]. This works on the raw value, as decoding the model fails
+    /// if the configuration is partially unknown and the model has fields that can't hold unknown values,
+    /// like a plain `String`.
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        let _ = (config, diags);
+        Ok(())
+    }
+    fn new(data: Self::ProviderData) -> DResult<Self>;
+}
+
+impl<Ds: TypedDataSource> DataSource for Ds {
+    type ProviderData = Ds::ProviderData;
+
+    async fn read(
+        &self,
+        config: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> DResult<Value> {
+        let config = Ds::Model::from_root_value(config)?;
+        let state = TypedDataSource::read(self, config, provider_meta, diags).await?;
+        Ok(state.to_value())
+    }
+
+    fn name(provider_name: &str) -> String {
+        Ds::name(provider_name)
+    }
+    fn schema() -> Schema {
+        Ds::schema()
+    }
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        Ds::validate_config(config, diags)
+    }
+    fn new(data: Self::ProviderData) -> DResult<Self> {
+        Ds::new(data)
+    }
+}
+
 pub(crate) trait DynDataSource: Send + Sync + 'static {
-    fn read(&self, config: Value) -> BoxFut<'_, DResult<Value>>;
+    fn read<'a>(
+        &'a self,
+        config: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>>;
 }
 
 impl<Ds: DataSource> DynDataSource for Ds {
-    fn read(&self, config: Value) -> BoxFut<'_, DResult<Value>> {
-        Box::pin(DataSource::read(self, config))
+    fn read<'a>(
+        &'a self,
+        config: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(DataSource::read(self, config, provider_meta, diags))
     }
 }
@@ -1,23 +1,34 @@
+/// A list of errors and warnings.
+/// Warnings can be reported alongside a successful result using the `diags` parameter of provider methods.
 #[derive(Debug, Default, Clone)]
 pub struct Diagnostics {
     pub(crate) diags: Vec<Diagnostic>,
-    // note: lol this cannot contain warnings that would be fucked oops
 }
 
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
+    pub(crate) severity: Severity,
+    /// A short summary of the problem, shown in the first line of the diagnostic.
     pub(crate) msg: String,
+    /// A longer explanation of the problem, which may be empty.
+    pub(crate) detail: String,
     pub(crate) attr: Option<AttrPath>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
 pub type DResult<T> = Result<T, Diagnostics>;
 
 // TODO: this could probably be a clever 0-alloc &-based linked list!
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct AttrPath(pub(crate) Vec<AttrPathSegment>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AttrPathSegment {
     AttributeName(String),
     ElementKeyString(String),
@@ -26,11 +37,32 @@ pub enum AttrPathSegment {
 
 impl Diagnostic {
     pub fn error_string(msg: impl Into<String>) -> Self {
+        Self::new(Severity::Error, msg.into(), String::new())
+    }
+    pub fn error(summary: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(Severity::Error, summary.into(), detail.into())
+    }
+    pub fn warning_string(msg: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, msg.into(), String::new())
+    }
+    pub fn warning(summary: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, summary.into(), detail.into())
+    }
+    fn new(severity: Severity, msg: String, detail: String) -> Self {
         Diagnostic {
-            msg: msg.into(),
+            severity,
+            msg,
+            detail,
             attr: None,
         }
     }
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
     pub fn with_path(mut self, path: AttrPath) -> Self {
         self.attr = Some(path);
         self
@@ -42,7 +74,16 @@ impl Diagnostics {
         self.diags.push(d);
     }
     pub fn has_errors(&self) -> bool {
-        !self.diags.is_empty()
+        self.diags.iter().any(|d| d.severity == Severity::Error)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.diags.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diags.iter()
+    }
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.diags.extend(other.diags);
     }
 }
 
@@ -58,6 +99,30 @@ impl AttrPath {
         p.0.push(AttrPathSegment::AttributeName(name));
         p
     }
+    pub fn append_element_key_string(&self, key: String) -> Self {
+        let mut p = self.clone();
+        p.0.push(AttrPathSegment::ElementKeyString(key));
+        p
+    }
+    pub fn append_element_key_int(&self, key: i64) -> Self {
+        let mut p = self.clone();
+        p.0.push(AttrPathSegment::ElementKeyInt(key));
+        p
+    }
+}
+
+impl std::fmt::Display for AttrPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                AttrPathSegment::AttributeName(name) if i == 0 => write!(f, "{name}")?,
+                AttrPathSegment::AttributeName(name) => write!(f, ".{name}")?,
+                AttrPathSegment::ElementKeyString(key) => write!(f, "[{key:?}]")?,
+                AttrPathSegment::ElementKeyInt(key) => write!(f, "[{key}]")?,
+            }
+        }
+        Ok(())
+    }
 }
 
 pub trait EyreExt<T> {
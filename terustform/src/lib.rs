@@ -1,9 +1,18 @@
+// Lets the derive macros, which refer to `::terustform`, be used in our own tests.
+extern crate self as terustform;
+
 // Internal modules
 mod server;
 
 // Modules re-exported in the root
 mod diag;
+mod model;
+mod number;
+mod plan_modifier;
+mod refinements;
 mod schema;
+mod set;
+mod validator;
 mod values;
 
 // Public modules
@@ -13,13 +22,19 @@ pub mod resource;
 
 // Re-exports
 pub use diag::*;
+pub use number::*;
+pub use plan_modifier::*;
+pub use refinements::*;
 pub use schema::*;
+pub use set::*;
+pub use validator::*;
 pub use values::*;
 
-pub use terustform_macros::Model;
+pub use terustform_macros::{Model, Schema};
 
 pub use async_trait::async_trait;
 pub use eyre;
+pub use regex;
 use tracing_subscriber::EnvFilter;
 
 // --------
@@ -70,13 +85,19 @@ macro_rules! attrs {
 pub mod __derive_private {
     pub use crate::{
         AttrPath, AttrPathSegment, BaseValue, DResult, Diagnostic, Diagnostics, Value, ValueKind,
-        ValueModel, Attribute,
+        ValueModel, Attribute, AttributeModel, Mode, PlanModifier, Schema, SchemaModel, Validator,
+    };
+    pub use {
+        std::collections::{BTreeMap, HashMap},
+        Clone, Default, Extend, FromIterator, Option::None, Option::Some, Result::Err, Result::Ok,
+        String, ToOwned, Vec,
     };
-    pub use {std::collections::HashMap, Clone, FromIterator, Option::Some, Result::Err, ToOwned};
 
-    pub fn new_object<const N: usize>(elems: [(&str, Value); N]) -> Value {
-        Value::Known(ValueKind::Object(std::collections::BTreeMap::from_iter(
-            elems.into_iter().map(|(k, v)| (k.to_owned(), v)),
-        )))
+    /// The fields of a model struct, which may be flattened into another model's object.
+    pub trait ObjectModel: Sized {
+        /// Takes the fields out of `obj`, leaving other attributes in it.
+        fn from_object(obj: &mut BTreeMap<String, Value>, path: &AttrPath) -> DResult<Self>;
+
+        fn to_object(self, obj: &mut BTreeMap<String, Value>);
     }
 }
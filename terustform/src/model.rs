@@ -0,0 +1,562 @@
+//! [`ValueModel`] and [`AttributeModel`] implementations for common Rust types.
+//!
+//! Plain types like `String` or `Vec<T>` must be known and not null.
+//! Wrap them in a [`BaseValue`] to allow unknown and null values, or an [`Option`] for just null values.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    AttrPath, Attribute, AttributeModel, BaseValue, DResult, Diagnostic, Diagnostics, Mode, Number,
+    PlanModifier, Validator, Value, ValueKind, ValueModel,
+};
+
+fn type_error(expected: &str, found: &Value, path: &AttrPath) -> Diagnostics {
+    let found = match found {
+        Value::Unknown(_) => "unknown",
+        Value::Null => "null",
+        Value::Known(v) => v.diagnostic_type_str(),
+    };
+    Diagnostic::error_string(format!("expected {expected}, found {found} value"))
+        .with_path(path.clone())
+        .into()
+}
+
+/// Decodes all elements, collecting the errors of every element.
+fn elements<E, T, C: FromIterator<T>>(
+    elems: impl IntoIterator<Item = (E, AttrPath)>,
+    from_value: impl Fn(E, &AttrPath) -> DResult<T>,
+) -> DResult<C> {
+    let mut errors = Diagnostics::default();
+    let elems = elems
+        .into_iter()
+        .filter_map(|(v, path)| match from_value(v, &path) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                errors.extend(e);
+                None
+            }
+        })
+        .collect::<C>();
+    if errors.has_errors() {
+        return Err(errors);
+    }
+    Ok(elems)
+}
+
+fn indexed(elems: Vec<Value>, path: &AttrPath) -> impl Iterator<Item = (Value, AttrPath)> + '_ {
+    elems
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| (v, path.append_element_key_int(i as i64)))
+}
+
+impl ValueModel for Value {
+    fn from_value(v: Value, _path: &AttrPath) -> DResult<Self> {
+        Ok(v)
+    }
+
+    fn to_value(self) -> Value {
+        self
+    }
+}
+
+impl<T: ValueModel> ValueModel for BaseValue<T> {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Unknown(refinements) => Ok(Self::Unknown(refinements)),
+            Value::Null => Ok(Self::Null),
+            v => T::from_value(v, path).map(Self::Known),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        match self {
+            Self::Unknown(refinements) => Value::Unknown(refinements),
+            Self::Null => Value::Null,
+            Self::Known(v) => v.to_value(),
+        }
+    }
+}
+
+/// `None` is null, unknown values are an error.
+impl<T: ValueModel> ValueModel for Option<T> {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Null => Ok(None),
+            v => T::from_value(v, path).map(Some),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        self.map_or(Value::Null, T::to_value)
+    }
+}
+
+impl ValueModel for String {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::String(s)) => Ok(s),
+            v => Err(type_error("string", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::String(self))
+    }
+}
+
+impl ValueModel for bool {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::Bool(b)) => Ok(b),
+            v => Err(type_error("bool", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::Bool(self))
+    }
+}
+
+impl ValueModel for Number {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::Number(n)) => Ok(n),
+            v => Err(type_error("number", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::Number(self))
+    }
+}
+
+/// Rounds numbers that don't fit into an `f64`, use [`Number`] to keep them exact.
+impl ValueModel for f64 {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        Number::from_value(v, path).map(|n| n.as_f64())
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::Number(self.into()))
+    }
+}
+
+/// Integers are checked to be in range of the Rust type.
+macro_rules! int_model {
+    ($($ty:ty),*) => {$(
+        impl ValueModel for $ty {
+            fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+                let n = Number::from_value(v, path)?;
+                if !n.is_integer() {
+                    return Err(Diagnostic::error_string(format!("expected an integer, found {n}"))
+                        .with_path(path.clone())
+                        .into());
+                }
+                let int = n.as_i64().and_then(|n| <$ty>::try_from(n).ok());
+                let int = int.or_else(|| n.as_u64().and_then(|n| <$ty>::try_from(n).ok()));
+                int.ok_or_else(|| {
+                    Diagnostic::error_string(format!(
+                        "{n} is out of range for {}, which must be between {} and {}",
+                        stringify!($ty),
+                        <$ty>::MIN,
+                        <$ty>::MAX
+                    ))
+                    .with_path(path.clone())
+                    .into()
+                })
+            }
+
+            fn to_value(self) -> Value {
+                Value::Known(ValueKind::Number(self.into()))
+            }
+        }
+    )*};
+}
+
+int_model!(i64, u64, i32, u32);
+
+impl<T: ValueModel> ValueModel for Vec<T> {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::List(elems)) => elements(indexed(elems, path), T::from_value),
+            v => Err(type_error("list", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::List(self.into_iter().map(T::to_value).collect()))
+    }
+}
+
+/// Set elements cannot be addressed in the protocol, so their errors point at the set.
+impl<T: ValueModel + Ord> ValueModel for BTreeSet<T> {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::Set(elems)) => {
+                let elems = elems.into_iter().map(|v| (v, path.clone()));
+                elements(elems, T::from_value)
+            }
+            v => Err(type_error("set", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::Set(self.into_iter().map(T::to_value).collect()))
+    }
+}
+
+impl<T: ValueModel> ValueModel for BTreeMap<String, T> {
+    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+        match v {
+            Value::Known(ValueKind::Map(elems)) => {
+                let elems = elems.into_iter().map(|(k, v)| {
+                    let path = path.append_element_key_string(k.clone());
+                    ((k, v), path)
+                });
+                elements(elems, |(k, v), path| Ok((k, T::from_value(v, path)?)))
+            }
+            v => Err(type_error("map", &v, path)),
+        }
+    }
+
+    fn to_value(self) -> Value {
+        Value::Known(ValueKind::Map(
+            self.into_iter().map(|(k, v)| (k, v.to_value())).collect(),
+        ))
+    }
+}
+
+macro_rules! tuple_model {
+    ($len:literal; $($ty:ident $var:ident $idx:tt),+) => {
+        impl<$($ty: ValueModel),+> ValueModel for ($($ty,)+) {
+            fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
+                let elems = match v {
+                    Value::Known(ValueKind::Tuple(elems)) if elems.len() == $len => elems,
+                    Value::Known(ValueKind::Tuple(elems)) => {
+                        return Err(Diagnostic::error_string(format!(
+                            "expected tuple with {} elements, found {} elements",
+                            $len,
+                            elems.len()
+                        ))
+                        .with_path(path.clone())
+                        .into());
+                    }
+                    v => return Err(type_error("tuple", &v, path)),
+                };
+
+                let mut errors = Diagnostics::default();
+                let mut elems = elems.into_iter();
+                let elems = ($(
+                    $ty::from_value(
+                        elems.next().unwrap_or(Value::Null),
+                        &path.append_element_key_int($idx),
+                    )
+                    .map_err(|e| errors.extend(e))
+                    .ok(),
+                )+);
+                match elems {
+                    ($(Some($var),)+) => Ok(($($var,)+)),
+                    _ => Err(errors),
+                }
+            }
+
+            fn to_value(self) -> Value {
+                Value::Known(ValueKind::Tuple(vec![$(self.$idx.to_value()),+]))
+            }
+        }
+    };
+}
+
+tuple_model!(1; A a 0);
+tuple_model!(2; A a 0, B b 1);
+tuple_model!(3; A a 0, B b 1, C c 2);
+tuple_model!(4; A a 0, B b 1, C c 2, D d 3);
+tuple_model!(5; A a 0, B b 1, C c 2, D d 3, E e 4);
+tuple_model!(6; A a 0, B b 1, C c 2, D d 3, E e 4, F f 5);
+
+// Attributes
+
+impl<T: AttributeModel> AttributeModel for BaseValue<T> {
+    fn attribute(
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+    ) -> Attribute {
+        T::attribute(description, mode, sensitive, validators, plan_modifiers)
+    }
+}
+
+impl<T: AttributeModel> AttributeModel for Option<T> {
+    fn attribute(
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+    ) -> Attribute {
+        T::attribute(description, mode, sensitive, validators, plan_modifiers)
+    }
+}
+
+macro_rules! primitive_attribute {
+    ($ty:ty, $variant:ident) => {
+        impl AttributeModel for $ty {
+            fn attribute(
+                description: String,
+                mode: Mode,
+                sensitive: bool,
+                validators: Vec<Validator>,
+                plan_modifiers: Vec<PlanModifier>,
+            ) -> Attribute {
+                Attribute::$variant {
+                    description,
+                    mode,
+                    sensitive,
+                    validators,
+                    plan_modifiers,
+                    default: None,
+                }
+            }
+        }
+    };
+}
+
+primitive_attribute!(String, String);
+primitive_attribute!(bool, Bool);
+primitive_attribute!(Number, Number);
+primitive_attribute!(f64, Float64);
+primitive_attribute!(i64, Int64);
+primitive_attribute!(i32, Int64);
+primitive_attribute!(u32, Int64);
+primitive_attribute!(u64, Number);
+primitive_attribute!(Value, Dynamic);
+
+/// Collections of objects become nested attributes, other collections get an element type.
+macro_rules! collection_attribute {
+    ($ty:ty, $variant:ident, $nested:ident) => {
+        impl<T: AttributeModel> AttributeModel for $ty {
+            fn attribute(
+                description: String,
+                mode: Mode,
+                sensitive: bool,
+                mut validators: Vec<Validator>,
+                plan_modifiers: Vec<PlanModifier>,
+            ) -> Attribute {
+                match T::attribute(String::new(), Mode::Optional, false, vec![], vec![]) {
+                    Attribute::Object { attrs, .. } => Attribute::$nested {
+                        description,
+                        mode,
+                        sensitive,
+                        validators,
+                        plan_modifiers,
+                        default: None,
+                        attrs,
+                    },
+                    elem => {
+                        if !elem.validators().is_empty() {
+                            validators.push(Validator::elements(elem.validators().to_vec()));
+                        }
+                        Attribute::$variant {
+                            description,
+                            mode,
+                            sensitive,
+                            validators,
+                            plan_modifiers,
+                            default: None,
+                            elem: elem.typ(),
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+collection_attribute!(Vec<T>, List, ListNested);
+collection_attribute!(BTreeSet<T>, Set, SetNested);
+collection_attribute!(BTreeMap<String, T>, Map, MapNested);
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::{AttrPath, BaseValue, I64Value, Number, StringValue, Value, ValueKind, ValueModel};
+
+    fn number(n: f64) -> Value {
+        Value::Known(ValueKind::Number(n.into()))
+    }
+
+    fn string(s: &str) -> Value {
+        Value::Known(ValueKind::String(s.into()))
+    }
+
+    fn error_paths<T: ValueModel>(v: Value) -> Vec<String> {
+        T::from_root_value(v)
+            .err()
+            .unwrap()
+            .iter()
+            .map(|diag| diag.attr.as_ref().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(
+            I64Value::from_root_value(number(3.0)).unwrap(),
+            I64Value::Known(3)
+        );
+        assert_eq!(
+            I64Value::from_root_value(Value::Null).unwrap(),
+            I64Value::Null
+        );
+        assert!(i64::from_root_value(number(1.5)).is_err());
+        assert!(i64::from_root_value(number(1e19)).is_err());
+        let huge = Value::Known(ValueKind::Number(Number::parse("1e400").unwrap()));
+        let err = i64::from_root_value(huge).unwrap_err();
+        assert!(err.iter().next().unwrap().msg.contains("out of range"));
+        assert!(i64::from_root_value(Value::unknown()).is_err());
+        assert_eq!(
+            i64::from_root_value(number(-9.223372036854776e18)).unwrap(),
+            i64::MIN
+        );
+    }
+
+    #[test]
+    fn optional() {
+        assert_eq!(Option::<bool>::from_root_value(Value::Null).unwrap(), None);
+        assert_eq!(
+            Option::<bool>::from_root_value(Value::Known(ValueKind::Bool(true))).unwrap(),
+            Some(true)
+        );
+        assert!(Option::<bool>::from_root_value(Value::unknown()).is_err());
+        assert_eq!(None::<String>.to_value(), Value::Null);
+    }
+
+    #[test]
+    fn collections() {
+        let list = Value::Known(ValueKind::List(vec![number(1.0), string("a"), number(0.5)]));
+        assert_eq!(error_paths::<Vec<i64>>(list), ["[1]", "[2]"]);
+
+        let set = Value::Known(ValueKind::Set(vec![string("b"), string("a")].into()));
+        let decoded = BTreeSet::<String>::from_root_value(set).unwrap();
+        assert_eq!(decoded, BTreeSet::from(["a".to_owned(), "b".to_owned()]));
+        let set = Value::Known(ValueKind::Set(vec![string("a"), number(1.0)].into()));
+        let map = Value::Known(ValueKind::Map(BTreeMap::from([("tags".to_owned(), set)])));
+        assert_eq!(
+            error_paths::<BTreeMap<String, BTreeSet<String>>>(map),
+            [r#"["tags"]"#]
+        );
+
+        let map = Value::Known(ValueKind::Map(BTreeMap::from([
+            ("ok".to_owned(), string("a")),
+            ("bad".to_owned(), number(1.0)),
+        ])));
+        assert_eq!(
+            error_paths::<BTreeMap<String, String>>(map.clone()),
+            ["[\"bad\"]"]
+        );
+        let decoded = BTreeMap::<String, Value>::from_root_value(map.clone()).unwrap();
+        assert_eq!(decoded.to_value(), map);
+
+        let tuple = Value::Known(ValueKind::Tuple(vec![string("a"), number(2.0)]));
+        let decoded = <(String, BaseValue<i64>)>::from_value(tuple.clone(), &AttrPath::root());
+        assert_eq!(decoded.unwrap(), ("a".to_owned(), BaseValue::Known(2)));
+        assert_eq!(error_paths::<(i64, i64)>(tuple), ["[0]"]);
+    }
+
+    #[derive(crate::Model, Debug, PartialEq)]
+    struct Common {
+        id: StringValue,
+    }
+
+    #[derive(crate::Model, Debug, PartialEq)]
+    struct Server {
+        #[terustform(flatten)]
+        common: Common,
+        #[terustform(rename = "type")]
+        kind: StringValue,
+        r#loop: StringValue,
+        #[terustform(default)]
+        region: StringValue,
+        #[terustform(skip)]
+        cache: Option<String>,
+    }
+
+    #[test]
+    fn derive_model_field_attrs() {
+        let value = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("id".into(), string("a")),
+            ("type".into(), string("b")),
+            ("loop".into(), string("c")),
+        ])));
+
+        let server = Server::from_root_value(value).unwrap();
+        assert_eq!(
+            server,
+            Server {
+                common: Common {
+                    id: StringValue::Known("a".into()),
+                },
+                kind: StringValue::Known("b".into()),
+                r#loop: StringValue::Known("c".into()),
+                region: StringValue::Null,
+                cache: None,
+            }
+        );
+
+        assert_eq!(
+            server.to_value(),
+            Value::Known(ValueKind::Object(BTreeMap::from([
+                ("id".into(), string("a")),
+                ("type".into(), string("b")),
+                ("loop".into(), string("c")),
+                ("region".into(), Value::Null),
+            ])))
+        );
+
+        let missing = Value::Known(ValueKind::Object(BTreeMap::from([(
+            "type".into(),
+            string("b"),
+        )])));
+        assert!(Server::from_root_value(missing).is_err());
+    }
+
+    #[test]
+    fn derive_model_collects_errors() {
+        let value = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("id".into(), Value::Known(ValueKind::Bool(true))),
+            ("loop".into(), Value::Known(ValueKind::Number(1.into()))),
+            ("extra".into(), Value::Null),
+        ])));
+        assert_eq!(
+            error_paths::<Server>(value),
+            ["id", "type", "loop", "extra"]
+        );
+    }
+
+    #[derive(crate::Model, Debug, PartialEq)]
+    enum Protocol {
+        #[terustform(rename = "tcp")]
+        Tcp,
+        #[terustform(rename = "udp")]
+        Udp,
+    }
+
+    #[test]
+    fn derive_model_enum() {
+        assert_eq!(
+            Protocol::from_root_value(string("udp")).unwrap(),
+            Protocol::Udp
+        );
+        assert_eq!(Protocol::Tcp.to_value(), string("tcp"));
+
+        let errs = Protocol::from_root_value(string("icmp")).unwrap_err();
+        let diag = errs.iter().next().unwrap();
+        assert_eq!(diag.msg, "Expected one of 'tcp', 'udp', found 'icmp'");
+
+        assert!(Protocol::from_root_value(Value::Null).is_err());
+    }
+}
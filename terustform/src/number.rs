@@ -0,0 +1,342 @@
+use std::{
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+};
+
+/// A Terraform number, which has arbitrary precision.
+///
+/// Numbers are stored losslessly: integers that fit into an `i64` or `u64` and numbers that survive a round trip
+/// through an `f64` are stored as such, everything else is kept as its decimal string,
+/// like Terraform's msgpack encoding does.
+/// Numbers are equal if they have the same decimal value, where an `f64` stands for its shortest decimal.
+#[derive(Debug, Clone)]
+pub struct Number(Repr);
+
+/// Normalized so that every number has exactly one representation where possible:
+/// `Int` is preferred over `UInt` over `Float` over `Big`.
+#[derive(Debug, Clone)]
+enum Repr {
+    Int(i64),
+    /// Only used for numbers larger than `i64::MAX`.
+    UInt(u64),
+    /// Never an integer in the range of `i64` or `u64`.
+    Float(f64),
+    /// A decimal number that can't be represented by any of the above without losing precision.
+    Big(String),
+}
+
+/// The canonical form of a finite decimal number, which is the same for all representations of a value:
+/// the value is `digits * 10^exponent`, and `digits` has no leading or trailing zeros.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Parses a decimal number like `-1.50` or `12e-3`.
+    fn parse(s: &str) -> Option<Self> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let digits = format!("{int}{frac}");
+        let exponent = exponent.checked_sub(i64::try_from(frac.len()).ok()?)?;
+        let trimmed = digits.trim_end_matches('0');
+        let exponent = exponent.checked_add((digits.len() - trimmed.len()) as i64)?;
+        let digits = trimmed.trim_start_matches('0');
+        if digits.is_empty() {
+            return Some(Self {
+                negative: false,
+                digits: String::new(),
+                exponent: 0,
+            });
+        }
+        Some(Self {
+            negative,
+            digits: digits.to_owned(),
+            exponent,
+        })
+    }
+
+    fn is_integer(&self) -> bool {
+        self.exponent >= 0
+    }
+
+    /// The digits of the integer, if it is one with at most 20 digits, which covers `i64` and `u64`.
+    fn integer(&self) -> Option<String> {
+        let exponent = usize::try_from(self.exponent).ok()?;
+        if self.digits.is_empty() {
+            return Some("0".to_owned());
+        }
+        if self.digits.len() + exponent > 20 {
+            return None;
+        }
+        let sign = if self.negative { "-" } else { "" };
+        Some(format!("{sign}{}{}", self.digits, "0".repeat(exponent)))
+    }
+}
+
+impl Number {
+    /// Parses a decimal number like `12`, `-1.5` or `1e100`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let is_decimal = !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+        if !is_decimal {
+            return None;
+        }
+
+        if let Ok(n) = s.parse::<i64>() {
+            return Some(Self::from(n));
+        }
+        if let Ok(n) = s.parse::<u64>() {
+            return Some(Self::from(n));
+        }
+        let decimal = Decimal::parse(s)?;
+        // Decimals survive a round trip through an `f64` if it is their shortest representation.
+        let f = s.parse::<f64>().ok()?;
+        let float = Self::from(f);
+        if float.decimal().as_ref() == Some(&decimal) {
+            Some(float)
+        } else {
+            Some(Self(Repr::Big(s.to_owned())))
+        }
+    }
+
+    /// The canonical decimal of the number, `None` for infinite and NaN floats.
+    fn decimal(&self) -> Option<Decimal> {
+        match &self.0 {
+            Repr::Int(n) => Decimal::parse(&n.to_string()),
+            Repr::UInt(n) => Decimal::parse(&n.to_string()),
+            Repr::Float(n) => Decimal::parse(&format!("{n:e}")),
+            Repr::Big(s) => Decimal::parse(s),
+        }
+    }
+
+    /// The number as an `i64`, if it is an integer in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.0 {
+            Repr::Int(n) => Some(n),
+            Repr::UInt(_) | Repr::Float(_) => None,
+            Repr::Big(_) => self.decimal()?.integer()?.parse().ok(),
+        }
+    }
+
+    /// The number as a `u64`, if it is an integer in range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.0 {
+            Repr::Int(n) => u64::try_from(n).ok(),
+            Repr::UInt(n) => Some(n),
+            Repr::Float(_) => None,
+            Repr::Big(_) => self.decimal()?.integer()?.parse().ok(),
+        }
+    }
+
+    /// The number as an `f64`, rounding if it's not exactly representable.
+    pub fn as_f64(&self) -> f64 {
+        match &self.0 {
+            &Repr::Int(n) => n as f64,
+            &Repr::UInt(n) => n as f64,
+            &Repr::Float(n) => n,
+            Repr::Big(s) => s.parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// The number as an `f64`, if that doesn't lose precision.
+    pub fn as_f64_exact(&self) -> Option<f64> {
+        match self.0 {
+            Repr::Int(n) => Some(n as f64).filter(|f| *f as i64 == n && *f != i64::MAX as f64),
+            Repr::UInt(n) => Some(n as f64).filter(|f| *f as u64 == n && *f != u64::MAX as f64),
+            Repr::Float(n) => Some(n),
+            Repr::Big(_) => None,
+        }
+    }
+
+    /// Whether the number is an integer, regardless of its size.
+    pub fn is_integer(&self) -> bool {
+        match &self.0 {
+            Repr::Int(_) | Repr::UInt(_) => true,
+            Repr::Float(n) => n.fract() == 0.0,
+            Repr::Big(_) => self.decimal().is_some_and(|decimal| decimal.is_integer()),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Self {
+        Self(Repr::Int(value))
+    }
+}
+
+impl From<u64> for Number {
+    fn from(value: u64) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => Self(Repr::Int(value)),
+            Err(_) => Self(Repr::UInt(value)),
+        }
+    }
+}
+
+impl From<i32> for Number {
+    fn from(value: i32) -> Self {
+        Self::from(i64::from(value))
+    }
+}
+
+impl From<u32> for Number {
+    fn from(value: u32) -> Self {
+        Self::from(i64::from(value))
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        // `i64::MAX as f64` and `u64::MAX as f64` round up to 2^63 and 2^64, which are out of range.
+        if value.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&value) {
+            Self(Repr::Int(value as i64))
+        } else if value.fract() == 0.0 && (0.0..u64::MAX as f64).contains(&value) {
+            Self(Repr::UInt(value as u64))
+        } else {
+            Self(Repr::Float(value))
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Int(a), Repr::Int(b)) => a == b,
+            (Repr::UInt(a), Repr::UInt(b)) => a == b,
+            // Floats are never zero, so equal bits are equal values. NaN is equal to itself.
+            (Repr::Float(a), Repr::Float(b)) => a.to_bits() == b.to_bits(),
+            _ => match (self.decimal(), other.decimal()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Equality is reflexive even for NaN, as floats are compared by their bits.
+impl Eq for Number {}
+
+/// Hashes the canonical decimal, so numbers that are equal have the same hash.
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match (&self.0, self.decimal()) {
+            (_, Some(decimal)) => decimal.hash(state),
+            (Repr::Float(n), None) => n.to_bits().hash(state),
+            (_, None) => unreachable!("only floats can be infinite or NaN"),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Repr::Int(n) => write!(f, "{n}"),
+            Repr::UInt(n) => write!(f, "{n}"),
+            Repr::Float(n) => write!(f, "{n}"),
+            Repr::Big(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::Number;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Number::parse("12"), Some(Number::from(12)));
+        assert_eq!(Number::parse("12.0"), Some(Number::from(12)));
+        assert_eq!(Number::parse("-1.50"), Some(Number::from(-1.5)));
+        assert_eq!(Number::parse("1e5"), Some(Number::from(100000)));
+        assert_eq!(Number::parse("0.1"), Some(Number::from(0.1)));
+        assert_eq!(
+            Number::parse("18446744073709551615"),
+            Some(Number::from(u64::MAX))
+        );
+        assert_eq!(Number::parse("meow"), None);
+        assert_eq!(Number::parse("inf"), None);
+        assert_eq!(Number::parse(""), None);
+
+        let big = "123456789012345678901234567890";
+        assert_eq!(Number::parse(big).unwrap().to_string(), big);
+        assert_eq!(Number::parse(big).unwrap().as_f64_exact(), None);
+        let precise = "3.14159265358979323846";
+        assert_eq!(Number::parse(precise).unwrap().to_string(), precise);
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(
+            Number::parse("0.30000000000000004"),
+            Some(Number::from(0.1 + 0.2))
+        );
+        assert_eq!(
+            Number::from((1_u64 << 63) as f64),
+            Number::from(1_u64 << 63)
+        );
+        assert_eq!(
+            Number::parse("1.50000000000000000001"),
+            Number::parse("15.0000000000000000001e-1")
+        );
+        assert_ne!(
+            Number::parse("1.50000000000000000001"),
+            Number::parse("1.5")
+        );
+        assert_eq!(Number::parse("1e30"), Some(Number::from(1e30)));
+        assert_eq!(Number::from(f64::NAN), Number::from(f64::NAN));
+
+        let hash = |n: &Number| {
+            let mut hasher = DefaultHasher::new();
+            n.hash(&mut hasher);
+            hasher.finish()
+        };
+        let big = Number::parse("123456789012345678901234567890").unwrap();
+        let same = Number::parse("1.23456789012345678901234567890e29").unwrap();
+        assert_eq!(big, same);
+        assert_eq!(hash(&big), hash(&same));
+        assert_eq!(hash(&Number::from(4.0)), hash(&Number::from(4)));
+    }
+
+    #[test]
+    fn conversions() {
+        let snowflake = 1229133604588470373_i64;
+        let n = Number::from(snowflake);
+        assert_eq!(n.as_i64(), Some(snowflake));
+        assert_eq!(n.as_f64_exact(), None);
+        assert_eq!(Number::from(u64::MAX).as_i64(), None);
+        assert_eq!(Number::from(-1).as_u64(), None);
+        assert_eq!(Number::from(1.5).as_i64(), None);
+        assert_eq!(Number::from(4.0).as_i64(), Some(4));
+        assert!(Number::from(1e30).is_integer());
+        assert!(!Number::from(0.5).is_integer());
+
+        let big = |s| Number::parse(s).unwrap();
+        assert!(big("1e400").is_integer());
+        assert!(!big("1.00000000000000000001").is_integer());
+        assert_eq!(big("9007199254740993.0").as_i64(), Some(9007199254740993));
+        assert_eq!(big("18446744073709551615.0").as_u64(), Some(u64::MAX));
+        assert_eq!(big("1e400").as_u64(), None);
+    }
+}
@@ -0,0 +1,677 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::{
+    AttrPath, Attribute, Block, DResult, Diagnostics, Refinements, Schema, Value, ValueKind,
+};
+
+type ModifyPlanFn = dyn Fn(&mut AttributePlan<'_>) -> DResult<()> + Send + Sync;
+type RequiresReplaceIfFn = dyn Fn(&AttributePlan<'_>) -> bool + Send + Sync;
+type DefaultFn = dyn Fn(&AttrPath) -> DResult<Value> + Send + Sync;
+
+/// The planned change of a single attribute, passed to [`PlanModifier`]s.
+pub struct AttributePlan<'a> {
+    pub path: &'a AttrPath,
+    /// The value of the attribute in the configuration.
+    pub config: &'a Value,
+    /// The value of the attribute in the prior state. Null if the resource is being created.
+    pub state: &'a Value,
+    /// The planned value of the attribute, which modifiers may change.
+    pub plan: Value,
+    /// Whether the resource is being created, in which case there is no prior state.
+    pub creating: bool,
+    /// Set this if the change to the attribute requires destroying and recreating the resource.
+    pub requires_replace: bool,
+}
+
+/// A modification of the planned value of an attribute, run when Terraform plans a resource change.
+///
+/// Modifiers of nested attributes run before the modifiers of their parent.
+/// Plan modifiers are not run when the resource is being destroyed.
+#[derive(Clone)]
+pub struct PlanModifier {
+    description: String,
+    modify: Arc<ModifyPlanFn>,
+}
+
+impl PlanModifier {
+    /// Creates a custom plan modifier. The `description` should describe what the modifier does.
+    pub fn custom(
+        description: impl Into<String>,
+        modify: impl Fn(&mut AttributePlan<'_>) -> DResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            modify: Arc::new(modify),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn modify(&self, plan: &mut AttributePlan<'_>) -> DResult<()> {
+        (self.modify)(plan)
+    }
+
+    /// Changing the value of the attribute of an existing resource replaces the resource.
+    pub fn requires_replace() -> Self {
+        Self::requires_replace_if(
+            "changing the value of this attribute requires replacing the resource",
+            |_| true,
+        )
+    }
+
+    /// Changing the value of the attribute of an existing resource replaces the resource
+    /// if `cond` returns true for the change.
+    pub fn requires_replace_if(
+        description: impl Into<String>,
+        cond: impl Fn(&AttributePlan<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let cond: Arc<RequiresReplaceIfFn> = Arc::new(cond);
+        Self::custom(description, move |plan| {
+            if !plan.creating && plan.plan != *plan.state && cond(plan) {
+                plan.requires_replace = true;
+            }
+            Ok(())
+        })
+    }
+
+    /// Uses the value from the prior state instead of an unknown value for a computed attribute
+    /// that is not set in the configuration. Use this for values that never change after creation,
+    /// like IDs, to avoid showing them as "(known after apply)" on every update.
+    pub fn use_state_for_unknown() -> Self {
+        Self::custom(
+            "the value of this attribute does not change once it is known",
+            |plan| {
+                if plan.plan.is_unknown() && !plan.config.is_unknown() && !plan.state.is_null() {
+                    plan.plan = plan.state.clone();
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Marks the unknown planned value of a computed attribute as definitely not null,
+    /// which lets Terraform plan expressions that depend on it more precisely.
+    pub fn not_null() -> Self {
+        Self::custom(
+            "the value of this attribute is never null once it is known",
+            |plan| {
+                if let Value::Unknown(refinements) = &mut plan.plan {
+                    refinements.not_null = true;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The value of a computed attribute when it is not set in the configuration.
+/// Defaults are applied before anything else during planning, so the plan shows the concrete value.
+#[derive(Clone)]
+pub enum DefaultValue {
+    Static(Value),
+    /// Computes the default value, receiving the path of the attribute.
+    Dynamic(Arc<DefaultFn>),
+}
+
+impl DefaultValue {
+    pub fn string(value: impl Into<String>) -> Self {
+        Self::Static(Value::Known(ValueKind::String(value.into())))
+    }
+
+    pub fn int64(value: i64) -> Self {
+        Self::Static(Value::Known(ValueKind::Number(value.into())))
+    }
+
+    pub fn float64(value: f64) -> Self {
+        Self::Static(Value::Known(ValueKind::Number(value.into())))
+    }
+
+    pub fn bool(value: bool) -> Self {
+        Self::Static(Value::Known(ValueKind::Bool(value)))
+    }
+
+    /// A default for any kind of attribute, like lists or objects.
+    /// The value must match the type of the attribute.
+    pub fn value(value: Value) -> Self {
+        Self::Static(value)
+    }
+
+    pub fn dynamic(default: impl Fn(&AttrPath) -> DResult<Value> + Send + Sync + 'static) -> Self {
+        Self::Dynamic(Arc::new(default))
+    }
+
+    pub fn get(&self, path: &AttrPath) -> DResult<Value> {
+        match self {
+            Self::Static(value) => Ok(value.clone()),
+            Self::Dynamic(default) => default(path),
+        }
+    }
+}
+
+static NULL: Value = Value::Null;
+/// Elements of unknown values are unknown, but refinements only apply to the value itself.
+static UNKNOWN: Value = Value::Unknown(Refinements {
+    not_null: false,
+    string_prefix: None,
+    number_lower_bound: None,
+    number_upper_bound: None,
+    length_lower_bound: None,
+    length_upper_bound: None,
+});
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Defaults,
+    Modifiers,
+}
+
+/// Computes the planned state from the proposed new state, the defaults and the plan modifiers.
+struct Planner {
+    phase: Phase,
+    creating: bool,
+    /// Computed attributes only become unknown if something about the resource changes.
+    has_changes: bool,
+    requires_replace: Vec<AttrPath>,
+    diags: Diagnostics,
+}
+
+impl Schema {
+    /// Plans the change of a resource with this schema. Defaults are applied, computed attributes that are not
+    /// set in the configuration become unknown if the resource changes, and the plan modifiers are applied.
+    /// Returns the planned state and the paths of the attributes that require replacing the resource.
+    pub(crate) fn plan(
+        &self,
+        config: &Value,
+        prior_state: &Value,
+        proposed_new_state: Value,
+    ) -> DResult<(Value, Vec<AttrPath>)> {
+        // Nothing to plan when the resource is being destroyed.
+        if proposed_new_state.is_null() {
+            return Ok((Value::Null, Vec::new()));
+        }
+
+        let mut planner = Planner {
+            phase: Phase::Defaults,
+            creating: prior_state.is_null(),
+            has_changes: false,
+            requires_replace: Vec::new(),
+            diags: Diagnostics::default(),
+        };
+        let root = AttrPath::root();
+        let (attrs, blocks) = (&self.attributes, &self.blocks);
+
+        let plan = planner.nested(
+            &root,
+            config,
+            prior_state,
+            proposed_new_state,
+            attrs,
+            blocks,
+        );
+        if planner.diags.has_errors() {
+            return Err(planner.diags);
+        }
+
+        // Removing an attribute with a default from the configuration is a change too,
+        // so this has to be checked after applying the defaults.
+        planner.phase = Phase::Modifiers;
+        planner.has_changes = *prior_state != plan;
+        let plan = planner.nested(&root, config, prior_state, plan, attrs, blocks);
+        if planner.diags.has_errors() {
+            return Err(planner.diags);
+        }
+        Ok((plan, planner.requires_replace))
+    }
+}
+
+impl Planner {
+    fn attr(
+        &mut self,
+        path: &AttrPath,
+        attr: &Attribute,
+        config: &Value,
+        state: &Value,
+        mut plan: Value,
+    ) -> Value {
+        if self.phase == Phase::Defaults {
+            if let (Some(default), true) = (attr.default(), config.is_null()) {
+                match default.get(path) {
+                    Ok(value) => plan = value,
+                    Err(errs) => self.diags.extend(errs),
+                }
+            }
+            if let Some((_, attrs)) = attr.nested() {
+                plan = self.nested(path, config, state, plan, attrs, &HashMap::new());
+            }
+            return plan;
+        }
+
+        // Attributes with defaults already got their planned value from the default.
+        let has_default = attr.default().is_some();
+        if self.has_changes && attr.mode().computed() && config.is_null() && !has_default {
+            plan = Value::unknown();
+        }
+
+        if let Some((_, attrs)) = attr.nested() {
+            plan = self.nested(path, config, state, plan, attrs, &HashMap::new());
+        }
+
+        let mut attr_plan = AttributePlan {
+            path,
+            config,
+            state,
+            plan,
+            creating: self.creating,
+            requires_replace: false,
+        };
+        for modifier in attr.plan_modifiers() {
+            if let Err(errs) = modifier.modify(&mut attr_plan) {
+                self.diags.extend(errs);
+            }
+        }
+        if attr_plan.requires_replace {
+            self.requires_replace.push(path.clone());
+        }
+        attr_plan.plan
+    }
+
+    /// Plans the objects of a nested attribute or block, which are either a single object
+    /// or the elements of a collection.
+    fn nested(
+        &mut self,
+        path: &AttrPath,
+        config: &Value,
+        state: &Value,
+        plan: Value,
+        attrs: &HashMap<String, Attribute>,
+        blocks: &HashMap<String, Block>,
+    ) -> Value {
+        let Value::Known(plan) = plan else {
+            return plan;
+        };
+
+        Value::Known(match plan {
+            ValueKind::Object(values) => ValueKind::Object(
+                values
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let path = path.append_attribute_name(name.clone());
+                        let config = object_attr(config, &name);
+                        let state = object_attr(state, &name);
+                        let value = if let Some(attr) = attrs.get(&name) {
+                            self.attr(&path, attr, config, state, value)
+                        } else if let Some(block) = blocks.get(&name) {
+                            let (attrs, blocks) = (&block.attributes, &block.blocks);
+                            self.nested(&path, config, state, value, attrs, blocks)
+                        } else {
+                            value
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            ),
+            ValueKind::List(elems) => ValueKind::List(
+                elems
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, elem)| {
+                        let path = path.append_element_key_int(i as i64);
+                        let config = list_elem(config, i);
+                        let state = list_elem(state, i);
+                        self.nested(&path, config, state, elem, attrs, blocks)
+                    })
+                    .collect(),
+            ),
+            // Set elements have no position, so they are paired with the elements of the configuration
+            // and prior state that are the same apart from computed attributes, like the framework does.
+            // They cannot be addressed in the protocol either, so they share the path of the set.
+            ValueKind::Set(elems) => ValueKind::Set(
+                elems
+                    .into_iter()
+                    .map(|elem| {
+                        let config = set_elem(config, &elem, attrs, blocks);
+                        let state = set_elem(state, &elem, attrs, blocks);
+                        self.nested(path, config, state, elem, attrs, blocks)
+                    })
+                    .collect(),
+            ),
+            ValueKind::Map(elems) => ValueKind::Map(
+                elems
+                    .into_iter()
+                    .map(|(key, elem)| {
+                        let path = path.append_element_key_string(key.clone());
+                        let config = map_elem(config, &key);
+                        let state = map_elem(state, &key);
+                        let elem = self.nested(&path, config, state, elem, attrs, blocks);
+                        (key, elem)
+                    })
+                    .collect(),
+            ),
+            plan => plan,
+        })
+    }
+}
+
+fn object_attr<'a>(value: &'a Value, name: &str) -> &'a Value {
+    match value {
+        Value::Known(ValueKind::Object(attrs)) => attrs.get(name).unwrap_or(&NULL),
+        Value::Unknown(_) => &UNKNOWN,
+        _ => &NULL,
+    }
+}
+
+fn list_elem(value: &Value, i: usize) -> &Value {
+    match value {
+        Value::Known(ValueKind::List(elems)) => elems.get(i).unwrap_or(&NULL),
+        Value::Unknown(_) => &UNKNOWN,
+        _ => &NULL,
+    }
+}
+
+fn set_elem<'a>(
+    value: &'a Value,
+    elem: &Value,
+    attrs: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+) -> &'a Value {
+    match value {
+        Value::Known(ValueKind::Set(elems)) => elems
+            .iter()
+            .find(|other| same_elem(other, elem, attrs, blocks))
+            .unwrap_or(&NULL),
+        Value::Unknown(_) => &UNKNOWN,
+        _ => &NULL,
+    }
+}
+
+/// Whether two nested values are equal apart from their computed attributes,
+/// whose values may differ between the configuration, the plan and the prior state.
+fn same_elem(
+    a: &Value,
+    b: &Value,
+    attrs: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+) -> bool {
+    let (Value::Known(a), Value::Known(b)) = (a, b) else {
+        return a == b;
+    };
+    let no_blocks = HashMap::new();
+    match (a, b) {
+        (ValueKind::Object(a), ValueKind::Object(b)) => {
+            fn get<'a>(values: &'a BTreeMap<String, Value>, name: &str) -> &'a Value {
+                values.get(name).unwrap_or(&NULL)
+            }
+            let attrs_match = attrs
+                .iter()
+                .filter(|(_, attr)| !attr.mode().computed())
+                .all(|(name, attr)| match attr.nested() {
+                    Some((_, attrs)) => same_elem(get(a, name), get(b, name), attrs, &no_blocks),
+                    None => get(a, name) == get(b, name),
+                });
+            attrs_match
+                && blocks.iter().all(|(name, block)| {
+                    same_elem(get(a, name), get(b, name), &block.attributes, &block.blocks)
+                })
+        }
+        (ValueKind::List(a), ValueKind::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_elem(a, b, attrs, blocks))
+        }
+        (ValueKind::Set(a), ValueKind::Set(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|a| b.iter().any(|b| same_elem(a, b, attrs, blocks)))
+        }
+        (ValueKind::Map(a), ValueKind::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same_elem(a, b, attrs, blocks)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn map_elem<'a>(value: &'a Value, key: &str) -> &'a Value {
+    match value {
+        Value::Known(ValueKind::Map(elems)) => elems.get(key).unwrap_or(&NULL),
+        Value::Unknown(_) => &UNKNOWN,
+        _ => &NULL,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AttrPath, Attribute, Mode, Refinements, Schema, Value, ValueKind};
+
+    use super::{DefaultValue, PlanModifier};
+
+    fn schema() -> Schema {
+        Schema {
+            attributes: crate::attrs! {
+                "id" => Attribute::String {
+                    description: String::new(),
+                    mode: Mode::Computed,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![PlanModifier::use_state_for_unknown()],
+                    default: None,
+                },
+                "name" => Attribute::String {
+                    description: String::new(),
+                    mode: Mode::Required,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![PlanModifier::requires_replace()],
+                    default: None,
+                },
+                "size" => Attribute::Int64 {
+                    description: String::new(),
+                    mode: Mode::Optional,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                },
+                "updated" => Attribute::String {
+                    description: String::new(),
+                    mode: Mode::Computed,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                },
+            },
+            ..Default::default()
+        }
+    }
+
+    fn object(id: Value, name: &str, size: f64, updated: Value) -> Value {
+        Value::Known(ValueKind::Object(
+            [
+                ("id", id),
+                ("name", Value::Known(ValueKind::String(name.to_owned()))),
+                ("size", Value::Known(ValueKind::Number(size.into()))),
+                ("updated", updated),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+        ))
+    }
+
+    fn string(s: &str) -> Value {
+        Value::Known(ValueKind::String(s.to_owned()))
+    }
+
+    #[test]
+    fn create() {
+        let config = object(Value::Null, "a", 1.0, Value::Null);
+        let proposed = object(Value::Null, "a", 1.0, Value::Null);
+
+        let (plan, requires_replace) = schema().plan(&config, &Value::Null, proposed).unwrap();
+        assert_eq!(plan, object(Value::unknown(), "a", 1.0, Value::unknown()));
+        assert!(requires_replace.is_empty());
+    }
+
+    #[test]
+    fn create_not_null() {
+        let mut schema = schema();
+        if let Some(Attribute::String { plan_modifiers, .. }) = schema.attributes.get_mut("id") {
+            plan_modifiers.push(PlanModifier::not_null());
+        }
+        let config = object(Value::Null, "a", 1.0, Value::Null);
+        let proposed = object(Value::Null, "a", 1.0, Value::Null);
+
+        let (plan, _) = schema.plan(&config, &Value::Null, proposed).unwrap();
+        let id = Value::Unknown(Refinements::not_null());
+        assert_eq!(plan, object(id, "a", 1.0, Value::unknown()));
+    }
+
+    #[test]
+    fn update() {
+        let state = object(string("1"), "a", 1.0, string("yesterday"));
+        let config = object(Value::Null, "a", 2.0, Value::Null);
+        let proposed = object(string("1"), "a", 2.0, string("yesterday"));
+
+        let (plan, requires_replace) = schema().plan(&config, &state, proposed).unwrap();
+        assert_eq!(plan, object(string("1"), "a", 2.0, Value::unknown()));
+        assert!(requires_replace.is_empty());
+    }
+
+    #[test]
+    fn no_changes() {
+        let state = object(string("1"), "a", 1.0, string("yesterday"));
+        let config = object(Value::Null, "a", 1.0, Value::Null);
+        let proposed = object(string("1"), "a", 1.0, string("yesterday"));
+
+        let (plan, requires_replace) = schema().plan(&config, &state, proposed).unwrap();
+        assert_eq!(plan, object(string("1"), "a", 1.0, string("yesterday")));
+        assert!(requires_replace.is_empty());
+    }
+
+    #[test]
+    fn replace() {
+        let state = object(string("1"), "a", 1.0, string("yesterday"));
+        let config = object(Value::Null, "b", 1.0, Value::Null);
+        let proposed = object(string("1"), "b", 1.0, string("yesterday"));
+
+        let (_, requires_replace) = schema().plan(&config, &state, proposed).unwrap();
+        assert_eq!(requires_replace, vec![AttrPath::attr("name")]);
+    }
+
+    #[test]
+    fn defaults() {
+        let schema = Schema {
+            attributes: crate::attrs! {
+                "port" => Attribute::Int64 {
+                    description: String::new(),
+                    mode: Mode::OptionalComputed,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: Some(DefaultValue::int64(443)),
+                },
+            },
+            ..Default::default()
+        };
+        let port = |port: Value| {
+            Value::Known(ValueKind::Object(
+                [("port".to_owned(), port)].into_iter().collect(),
+            ))
+        };
+        let number = |n: f64| Value::Known(ValueKind::Number(n.into()));
+
+        let (plan, _) = schema
+            .plan(&port(Value::Null), &Value::Null, port(Value::Null))
+            .unwrap();
+        assert_eq!(plan, port(number(443.0)));
+
+        // Terraform proposes the prior state for unset computed attributes.
+        let (plan, _) = schema
+            .plan(&port(Value::Null), &port(number(80.0)), port(number(80.0)))
+            .unwrap();
+        assert_eq!(plan, port(number(443.0)));
+
+        let (plan, _) = schema
+            .plan(
+                &port(number(80.0)),
+                &port(number(443.0)),
+                port(number(80.0)),
+            )
+            .unwrap();
+        assert_eq!(plan, port(number(80.0)));
+    }
+
+    #[test]
+    fn set_elements_paired_by_value() {
+        let schema = Schema {
+            attributes: crate::attrs! {
+                "rules" => Attribute::SetNested {
+                    description: String::new(),
+                    mode: Mode::Required,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                    attrs: crate::attrs! {
+                        "id" => Attribute::String {
+                            description: String::new(),
+                            mode: Mode::Computed,
+                            sensitive: false,
+                            validators: vec![],
+                            plan_modifiers: vec![PlanModifier::use_state_for_unknown()],
+                            default: None,
+                        },
+                        "name" => Attribute::String {
+                            description: String::new(),
+                            mode: Mode::Required,
+                            sensitive: false,
+                            validators: vec![],
+                            plan_modifiers: vec![],
+                            default: None,
+                        },
+                    },
+                },
+            },
+            ..Default::default()
+        };
+        let rule = |name: &str, id: Value| {
+            Value::Known(ValueKind::Object(
+                [("id".to_owned(), id), ("name".to_owned(), string(name))]
+                    .into_iter()
+                    .collect(),
+            ))
+        };
+        let rules = |rules: Vec<Value>| {
+            Value::Known(ValueKind::Object(
+                [(
+                    "rules".to_owned(),
+                    Value::Known(ValueKind::Set(rules.into())),
+                )]
+                .into_iter()
+                .collect(),
+            ))
+        };
+
+        let state = rules(vec![rule("a", string("1")), rule("b", string("2"))]);
+        let config = rules(vec![
+            rule("b", Value::Null),
+            rule("a", Value::Null),
+            rule("c", Value::Null),
+        ]);
+
+        let (plan, _) = schema.plan(&config, &state, config.clone()).unwrap();
+        assert_eq!(
+            plan,
+            rules(vec![
+                rule("a", string("1")),
+                rule("b", string("2")),
+                rule("c", Value::unknown()),
+            ])
+        );
+    }
+}
@@ -2,8 +2,8 @@ use std::{future::Future, pin::Pin, sync::Arc};
 
 use crate::{
     datasource::{DataSource, DynDataSource},
-    resource::{DynResource, Resource},
-    DResult, Schema, Value,
+    resource::{DynResource, Resource, StateUpgrader},
+    DResult, Diagnostics, Schema, Value,
 };
 
 // This setup is a bit complicated.
@@ -19,16 +19,19 @@ pub trait ProviderData: Clone + Send + Sync + 'static {}
 impl<D: Clone + Send + Sync + 'static> ProviderData for D {}
 
 pub(super) type BoxFut<'a, O> = Pin<Box<dyn Future<Output = O> + Send + Sync + 'a>>;
+pub(crate) type ValidateConfigFn = fn(&Value, &mut Diagnostics) -> DResult<()>;
 
 pub struct MkDataSource<D: ProviderData> {
     pub(crate) name: fn(&str) -> String,
     pub(crate) schema: Schema,
+    pub(crate) validate_config: ValidateConfigFn,
     pub(crate) mk: fn(D) -> DResult<StoredDataSource>,
 }
 
 pub(crate) struct StoredDataSource {
     pub(crate) ds: Arc<dyn DynDataSource>,
     pub(crate) schema: Schema,
+    pub(crate) validate_config: ValidateConfigFn,
 }
 
 impl Clone for StoredDataSource {
@@ -36,6 +39,7 @@ impl Clone for StoredDataSource {
         Self {
             ds: self.ds.clone(),
             schema: self.schema.clone(),
+            validate_config: self.validate_config,
         }
     }
 }
@@ -45,10 +49,12 @@ impl<D: ProviderData> MkDataSource<D> {
         Self {
             name: Ds::name,
             schema: Ds::schema(),
+            validate_config: Ds::validate_config,
             mk: |data| {
                 Ok(StoredDataSource {
                     ds: Arc::new(Ds::new(data)?),
                     schema: Ds::schema(),
+                    validate_config: Ds::validate_config,
                 })
             },
         }
@@ -58,12 +64,16 @@ impl<D: ProviderData> MkDataSource<D> {
 pub struct MkResource<D: ProviderData> {
     pub(crate) name: fn(&str) -> String,
     pub(crate) schema: Schema,
+    pub(crate) validate_config: ValidateConfigFn,
+    /// Used to upgrade state before the provider has been configured.
+    pub(crate) state_upgraders: Vec<StateUpgrader>,
     pub(crate) mk: fn(D) -> DResult<StoredResource>,
 }
 
 pub(crate) struct StoredResource {
     pub(crate) rs: Arc<dyn DynResource>,
     pub(crate) schema: Schema,
+    pub(crate) validate_config: ValidateConfigFn,
 }
 
 impl Clone for StoredResource {
@@ -71,6 +81,7 @@ impl Clone for StoredResource {
         Self {
             rs: self.rs.clone(),
             schema: self.schema.clone(),
+            validate_config: self.validate_config,
         }
     }
 }
@@ -80,10 +91,13 @@ impl<D: ProviderData> MkResource<D> {
         Self {
             name: Rs::name,
             schema: Rs::schema(),
+            validate_config: Rs::validate_config,
+            state_upgraders: Rs::state_upgraders(),
             mk: |data| {
                 Ok(StoredResource {
                     rs: Arc::new(Rs::new(data)?),
                     schema: Rs::schema(),
+                    validate_config: Rs::validate_config,
                 })
             },
         }
@@ -97,7 +111,23 @@ pub trait Provider: Send + Sync + Sized + 'static {
     type Data: ProviderData;
     fn name(&self) -> String;
     fn schema(&self) -> Schema;
-    fn configure(&self, config: Value) -> impl Future<Output = DResult<Self::Data>> + Send;
+    /// The schema of the `provider_meta "name" { ... }` block that modules can use to pass
+    /// information to the provider. The decoded value is passed to all resources and data sources.
+    fn meta_schema(&self) -> Option<Schema> {
+        None
+    }
+    /// Validates the provider configuration beyond what the validators in the schema do.
+    /// The configuration may contain unknown values.
+    /// Warnings can be pushed to `diags`, they are reported whether the method succeeds or not.
+    fn validate_config(&self, config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        let _ = (config, diags);
+        Ok(())
+    }
+    fn configure(
+        &self,
+        config: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Self::Data>> + Send;
     fn data_sources(&self) -> DataSources<Self>;
     fn resources(&self) -> Resources<Self>;
 }
@@ -0,0 +1,155 @@
+//! Refinements narrow down the possible values of an unknown value, which Terraform 1.6+ uses for more precise plans.
+// https://github.com/hashicorp/terraform/blob/main/docs/plugin-protocol/object-wire-format.md#unknown-values-with-refinements
+
+use std::io;
+
+use crate::{DResult, Diagnostic, Number, Type, Value, ValueKind};
+
+/// The msgpack extension type of unknown values with refinements.
+const REFINED_UNKNOWN_EXT: i8 = 12;
+
+/// `false` means the value is definitely not null.
+const IS_NULL: i64 = 1;
+const STRING_PREFIX: i64 = 2;
+const NUMBER_LOWER_BOUND: i64 = 3;
+const NUMBER_UPPER_BOUND: i64 = 4;
+const LENGTH_LOWER_BOUND: i64 = 5;
+const LENGTH_UPPER_BOUND: i64 = 6;
+
+/// What is known about an unknown value. The default is no refinements at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Refinements {
+    /// The value will definitely not be null once it is known.
+    pub not_null: bool,
+    /// The string will start with this prefix.
+    pub string_prefix: Option<String>,
+    /// The number will be greater than this bound, or equal if the bound is inclusive (`true`).
+    pub number_lower_bound: Option<(Number, bool)>,
+    /// The number will be less than this bound, or equal if the bound is inclusive (`true`).
+    pub number_upper_bound: Option<(Number, bool)>,
+    /// The collection will have at least this many elements.
+    pub length_lower_bound: Option<u64>,
+    /// The collection will have at most this many elements.
+    pub length_upper_bound: Option<u64>,
+}
+
+impl Refinements {
+    /// Refinements for a value that will definitely not be null.
+    pub fn not_null() -> Self {
+        Self {
+            not_null: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Writes an unknown value with these refinements.
+    pub(crate) fn msg_pack(&self, wr: &mut Vec<u8>) -> DResult<()> {
+        use rmp::encode as mp;
+
+        if self.is_empty() {
+            wr.extend_from_slice(&[0xd4, 0, 0]);
+            return Ok(());
+        }
+
+        let mut payload = Vec::new();
+        let mut len = 0;
+        if self.not_null {
+            len += 1;
+            mp::write_sint(&mut payload, IS_NULL)?;
+            mp::write_bool(&mut payload, false)?;
+        }
+        if let Some(prefix) = &self.string_prefix {
+            len += 1;
+            mp::write_sint(&mut payload, STRING_PREFIX)?;
+            mp::write_str(&mut payload, prefix)?;
+        }
+        for (key, bound) in [
+            (NUMBER_LOWER_BOUND, &self.number_lower_bound),
+            (NUMBER_UPPER_BOUND, &self.number_upper_bound),
+        ] {
+            if let Some((n, inclusive)) = bound {
+                len += 1;
+                mp::write_sint(&mut payload, key)?;
+                mp::write_array_len(&mut payload, 2)?;
+                Value::Known(ValueKind::Number(n.clone()))
+                    .msg_pack_inner(&mut payload, &Type::Number)?;
+                mp::write_bool(&mut payload, *inclusive)?;
+            }
+        }
+        for (key, bound) in [
+            (LENGTH_LOWER_BOUND, self.length_lower_bound),
+            (LENGTH_UPPER_BOUND, self.length_upper_bound),
+        ] {
+            if let Some(bound) = bound {
+                len += 1;
+                mp::write_sint(&mut payload, key)?;
+                mp::write_uint(&mut payload, bound)?;
+            }
+        }
+
+        let mut map = Vec::new();
+        mp::write_map_len(&mut map, len)?;
+        map.extend_from_slice(&payload);
+
+        mp::write_ext_meta(wr, map.len() as u32, REFINED_UNKNOWN_EXT)?;
+        wr.extend_from_slice(&map);
+        Ok(())
+    }
+
+    /// Reads the refinements of an unknown value from the payload of its msgpack extension.
+    /// Other extension types are unknown values without refinements.
+    pub(crate) fn msg_unpack(ext_type: i8, payload: &[u8]) -> DResult<Self> {
+        use rmp::decode as mp;
+
+        let mut this = Self::default();
+        if ext_type != REFINED_UNKNOWN_EXT {
+            return Ok(this);
+        }
+
+        let rd = &mut io::Cursor::new(payload);
+        let len = mp::read_map_len(rd)?;
+        for _ in 0..len {
+            match mp::read_int::<i64, _>(rd)? {
+                IS_NULL => this.not_null = !mp::read_bool(rd)?,
+                STRING_PREFIX => {
+                    let len = mp::read_str_len(rd)?;
+                    let prefix = payload
+                        .get(rd.position() as usize..)
+                        .and_then(|rest| rest.get(..len as usize))
+                        .ok_or_else(|| Diagnostic::error_string("truncated string prefix"))?;
+                    this.string_prefix = Some(String::from_utf8(prefix.to_vec())?);
+                    rd.set_position(rd.position() + u64::from(len));
+                }
+                key @ (NUMBER_LOWER_BOUND | NUMBER_UPPER_BOUND) => {
+                    let len = mp::read_array_len(rd)?;
+                    if len != 2 {
+                        return Err(Diagnostic::error_string(format!(
+                            "expected 2 elems, found {len} elems in number bound"
+                        ))
+                        .into());
+                    }
+                    let Value::Known(ValueKind::Number(n)) =
+                        Value::msg_unpack_inner(rd, &Type::Number)?
+                    else {
+                        return Err(Diagnostic::error_string("expected number bound").into());
+                    };
+                    let bound = Some((n, mp::read_bool(rd)?));
+                    if key == NUMBER_LOWER_BOUND {
+                        this.number_lower_bound = bound;
+                    } else {
+                        this.number_upper_bound = bound;
+                    }
+                }
+                LENGTH_LOWER_BOUND => this.length_lower_bound = Some(mp::read_int(rd)?),
+                LENGTH_UPPER_BOUND => this.length_upper_bound = Some(mp::read_int(rd)?),
+                // We can't skip values of refinements we don't know, but ignoring the rest is always safe.
+                _ => break,
+            }
+        }
+        Ok(this)
+    }
+}
@@ -1,9 +1,13 @@
-use std::future::Future;
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+};
 
 use crate::{
     provider::{BoxFut, MkResource, ProviderData},
-    values::Value,
-    Schema,
+    values::{Value, ValueKind},
+    AttrPath, Attribute, Block, BlockNesting, Diagnostic, Diagnostics, Schema, ValueModel,
+    ValueSet,
 };
 
 use super::DResult;
@@ -12,22 +16,101 @@ pub trait Resource: Sized + Send + Sync + 'static {
     type ProviderData: ProviderData;
 
     // todo: probably want some kind of Value+Schema thing like tfsdk? whatever.
-    fn read(&self, current_state: Value) -> impl Future<Output = DResult<Value>> + Send + Sync;
+    /// Reads the current state of the resource.
+    /// Returns `None` if the resource no longer exists, which removes it from the state
+    /// so that Terraform plans to create it again.
+    /// `provider_meta` is the value of the `provider_meta` block, null if there is none.
+    /// Warnings can be pushed to `diags`, they are reported whether the method succeeds or not.
+    fn read(
+        &self,
+        current_state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Option<Value>>> + Send + Sync;
     fn create(
         &self,
         config: Value,
         plan: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
     ) -> impl Future<Output = DResult<Value>> + Send + Sync;
     fn update(
         &self,
         config: Value,
         plan: Value,
         state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
     ) -> impl Future<Output = DResult<Value>> + Send + Sync;
-    fn delete(&self, state: Value) -> impl Future<Output = DResult<Value>> + Send + Sync;
+    fn delete(
+        &self,
+        state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync;
+    /// Imports an existing resource by its import ID, returning its state.
+    /// Terraform will call `read` on the returned state afterwards, so it's sufficient to only fill
+    /// in the attributes necessary for `read`, see [`import_passthrough_id`].
+    ///
+    /// The default implementation does not support importing.
+    fn import(
+        &self,
+        id: String,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync {
+        let _ = (id, diags);
+        async { Err(Diagnostic::error_string("Resource does not support import").into()) }
+    }
+
+    /// Modifies the plan for a change of the resource, after the plan modifiers of the attributes ran.
+    /// This can adjust planned values, for example to mark attributes that depend on changed
+    /// attributes as unknown, add paths to `requires_replace` or reject the change with an error.
+    ///
+    /// The planned state is null if the resource is being destroyed, and the prior state is null
+    /// if it's being created.
+    ///
+    /// The default implementation returns the plan unchanged.
+    fn modify_plan(
+        &self,
+        config: Value,
+        prior_state: Value,
+        plan: ResourcePlan,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<ResourcePlan>> + Send + Sync {
+        let _ = (config, prior_state, provider_meta, diags);
+        async { Ok(plan) }
+    }
+
+    /// Upgrades state stored by an older `version` of the schema to the current schema.
+    /// This is only called when `version` is smaller than the version of [`Resource::schema`],
+    /// and only after the provider has been configured. Before that, the [`Resource::state_upgraders`] are used.
+    ///
+    /// The default implementation runs the [`Resource::state_upgraders`] one after another.
+    fn upgrade_state(
+        &self,
+        version: i64,
+        state: RawState,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync {
+        let _ = diags;
+        async move { upgrade_state_chain(&Self::schema(), &Self::state_upgraders(), version, state) }
+    }
+    /// The upgraders for every previous schema version, used by the default [`Resource::upgrade_state`].
+    /// Terraform may upgrade state before the provider is configured, in which case these are run directly.
+    fn state_upgraders() -> Vec<StateUpgrader> {
+        Vec::new()
+    }
 
     fn name(provider_name: &str) -> String;
     fn schema() -> Schema;
+    /// Validates the configuration of the resource beyond what the validators in the schema do.
+    /// This may be called before the provider is configured, so it cannot access the resource.
+    /// The configuration may contain unknown values.
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        let _ = (config, diags);
+        Ok(())
+    }
     fn new(data: Self::ProviderData) -> DResult<Self>;
 
     fn erase() -> MkResource<Self::ProviderData> {
@@ -36,23 +119,407 @@ pub trait Resource: Sized + Send + Sync + 'static {
 }
 
 pub(crate) trait DynResource: Send + Sync + 'static {
-    fn read(&self, current_state: Value) -> BoxFut<'_, DResult<Value>>;
-    fn create(&self, config: Value, plan: Value) -> BoxFut<'_, DResult<Value>>;
-    fn update(&self, config: Value, plan: Value, state: Value) -> BoxFut<'_, DResult<Value>>;
-    fn delete(&self, state: Value) -> BoxFut<'_, DResult<Value>>;
+    fn read<'a>(
+        &'a self,
+        current_state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Option<Value>>>;
+    fn create<'a>(
+        &'a self,
+        config: Value,
+        plan: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>>;
+    fn update<'a>(
+        &'a self,
+        config: Value,
+        plan: Value,
+        state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>>;
+    fn delete<'a>(
+        &'a self,
+        state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>>;
+    fn import<'a>(&'a self, id: String, diags: &'a mut Diagnostics) -> BoxFut<'a, DResult<Value>>;
+    fn modify_plan<'a>(
+        &'a self,
+        config: Value,
+        prior_state: Value,
+        plan: ResourcePlan,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<ResourcePlan>>;
+    fn upgrade_state<'a>(
+        &'a self,
+        version: i64,
+        state: RawState,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>>;
 }
 
 impl<R: Resource> DynResource for R {
-    fn read(&self, current_state: Value) -> BoxFut<'_, DResult<Value>> {
-        Box::pin(Resource::read(self, current_state))
+    fn read<'a>(
+        &'a self,
+        current_state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Option<Value>>> {
+        Box::pin(Resource::read(self, current_state, provider_meta, diags))
+    }
+    fn create<'a>(
+        &'a self,
+        config: Value,
+        plan: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(Resource::create(self, config, plan, provider_meta, diags))
+    }
+    fn update<'a>(
+        &'a self,
+        config: Value,
+        plan: Value,
+        state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(Resource::update(
+            self,
+            config,
+            plan,
+            state,
+            provider_meta,
+            diags,
+        ))
+    }
+    fn delete<'a>(
+        &'a self,
+        state: Value,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(Resource::delete(self, state, provider_meta, diags))
+    }
+    fn import<'a>(&'a self, id: String, diags: &'a mut Diagnostics) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(Resource::import(self, id, diags))
+    }
+    fn modify_plan<'a>(
+        &'a self,
+        config: Value,
+        prior_state: Value,
+        plan: ResourcePlan,
+        provider_meta: Value,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<ResourcePlan>> {
+        Box::pin(Resource::modify_plan(
+            self,
+            config,
+            prior_state,
+            plan,
+            provider_meta,
+            diags,
+        ))
+    }
+    fn upgrade_state<'a>(
+        &'a self,
+        version: i64,
+        state: RawState,
+        diags: &'a mut Diagnostics,
+    ) -> BoxFut<'a, DResult<Value>> {
+        Box::pin(Resource::upgrade_state(self, version, state, diags))
+    }
+}
+
+/// A [`Resource`] that works with a typed model instead of raw values.
+/// The framework decodes the config, plan and state into the model and encodes the returned model,
+/// reporting decoding errors with the path of the offending attribute.
+///
+/// Types implementing this trait implement [`Resource`] automatically.
+/// Implement [`Resource`] directly for dynamic cases where the shape of the values isn't known statically.
+pub trait TypedResource: Sized + Send + Sync + 'static {
+    type ProviderData: ProviderData;
+    /// The model of the config, plan and state, matching [`TypedResource::schema`].
+    /// Fields that can be unknown, like computed attributes in the plan or attributes set from
+    /// other resources, must use [`crate::BaseValue`], as plain types like `String` fail to decode unknown values.
+    type Model: ValueModel + Send + Sync;
+
+    /// Reads the current state of the resource.
+    /// Returns `None` if the resource no longer exists, see [`Resource::read`].
+    fn read(
+        &self,
+        current_state: Self::Model,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Option<Self::Model>>> + Send + Sync;
+    fn create(
+        &self,
+        config: Self::Model,
+        plan: Self::Model,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Self::Model>> + Send + Sync;
+    fn update(
+        &self,
+        config: Self::Model,
+        plan: Self::Model,
+        state: Self::Model,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Self::Model>> + Send + Sync;
+    fn delete(
+        &self,
+        state: Self::Model,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<()>> + Send + Sync;
+    /// See [`Resource::import`]. The default implementation does not support importing.
+    fn import(
+        &self,
+        id: String,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Self::Model>> + Send + Sync {
+        let _ = (id, diags);
+        async { Err(Diagnostic::error_string("Resource does not support import").into()) }
+    }
+    /// See [`Resource::modify_plan`]. This works on the raw values, as plans usually contain
+    /// unknown values that fail to decode into models with fields like a plain `String`.
+    ///
+    /// The default implementation returns the plan unchanged.
+    fn modify_plan(
+        &self,
+        config: Value,
+        prior_state: Value,
+        plan: ResourcePlan,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<ResourcePlan>> + Send + Sync {
+        let _ = (config, prior_state, provider_meta, diags);
+        async { Ok(plan) }
     }
-    fn create(&self, config: Value, plan: Value) -> BoxFut<'_, DResult<Value>> {
-        Box::pin(Resource::create(self, config, plan))
+    /// See [`Resource::upgrade_state`], state upgrades work on raw values as the old state
+    /// doesn't match the model anymore.
+    fn upgrade_state(
+        &self,
+        version: i64,
+        state: RawState,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync {
+        let _ = diags;
+        async move { upgrade_state_chain(&Self::schema(), &Self::state_upgraders(), version, state) }
+    }
+    fn state_upgraders() -> Vec<StateUpgrader> {
+        Vec::new()
+    }
+
+    fn name(provider_name: &str) -> String;
+    fn schema() -> Schema;
+    /// See [`Resource::validate_config`]. This works on the raw value, as decoding the model fails
+    /// if the configuration is partially unknown and the model has fields that can't hold unknown values,
+    /// like a plain `String`.
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        let _ = (config, diags);
+        Ok(())
+    }
+    fn new(data: Self::ProviderData) -> DResult<Self>;
+}
+
+impl<R: TypedResource> Resource for R {
+    type ProviderData = R::ProviderData;
+
+    async fn read(
+        &self,
+        current_state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> DResult<Option<Value>> {
+        let current_state = R::Model::from_root_value(current_state)?;
+        let new_state = TypedResource::read(self, current_state, provider_meta, diags).await?;
+        Ok(new_state.map(ValueModel::to_value))
+    }
+    async fn create(
+        &self,
+        config: Value,
+        plan: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> DResult<Value> {
+        let config = R::Model::from_root_value(config)?;
+        let plan = R::Model::from_root_value(plan)?;
+        let new_state = TypedResource::create(self, config, plan, provider_meta, diags).await?;
+        Ok(new_state.to_value())
     }
-    fn update(&self, config: Value, plan: Value, state: Value) -> BoxFut<'_, DResult<Value>> {
-        Box::pin(Resource::update(self, config, plan, state))
+    async fn update(
+        &self,
+        config: Value,
+        plan: Value,
+        state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> DResult<Value> {
+        let config = R::Model::from_root_value(config)?;
+        let plan = R::Model::from_root_value(plan)?;
+        let state = R::Model::from_root_value(state)?;
+        let new_state =
+            TypedResource::update(self, config, plan, state, provider_meta, diags).await?;
+        Ok(new_state.to_value())
     }
-    fn delete(&self, state: Value) -> BoxFut<'_, DResult<Value>> {
-        Box::pin(Resource::delete(self, state))
+    async fn delete(
+        &self,
+        state: Value,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> DResult<Value> {
+        let state = R::Model::from_root_value(state)?;
+        TypedResource::delete(self, state, provider_meta, diags).await?;
+        Ok(Value::Null)
+    }
+    async fn import(&self, id: String, diags: &mut Diagnostics) -> DResult<Value> {
+        let state = TypedResource::import(self, id, diags).await?;
+        Ok(state.to_value())
+    }
+    fn modify_plan(
+        &self,
+        config: Value,
+        prior_state: Value,
+        plan: ResourcePlan,
+        provider_meta: Value,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<ResourcePlan>> + Send + Sync {
+        TypedResource::modify_plan(self, config, prior_state, plan, provider_meta, diags)
     }
+    fn upgrade_state(
+        &self,
+        version: i64,
+        state: RawState,
+        diags: &mut Diagnostics,
+    ) -> impl Future<Output = DResult<Value>> + Send + Sync {
+        TypedResource::upgrade_state(self, version, state, diags)
+    }
+    fn state_upgraders() -> Vec<StateUpgrader> {
+        R::state_upgraders()
+    }
+
+    fn name(provider_name: &str) -> String {
+        R::name(provider_name)
+    }
+    fn schema() -> Schema {
+        R::schema()
+    }
+    fn validate_config(config: &Value, diags: &mut Diagnostics) -> DResult<()> {
+        R::validate_config(config, diags)
+    }
+    fn new(data: Self::ProviderData) -> DResult<Self> {
+        R::new(data)
+    }
+}
+
+/// The planned change of a resource, see [`Resource::modify_plan`].
+#[derive(Debug, Clone)]
+pub struct ResourcePlan {
+    /// The planned new state of the resource, null if it's being destroyed.
+    /// Unknown values will be set by the provider during apply.
+    pub planned_state: Value,
+    /// The attributes whose change requires destroying and recreating the resource.
+    pub requires_replace: Vec<AttrPath>,
+}
+
+/// The state of a resource as stored by Terraform, before it has been upgraded to the current schema.
+#[derive(Debug, Clone)]
+pub enum RawState {
+    /// The JSON encoded state, used by all modern Terraform versions.
+    Json(serde_json::Value),
+    /// The legacy flatmap format used by Terraform 0.11 and older, mapping flattened attribute paths like
+    /// `rule.0.port` to their string values.
+    Flatmap(HashMap<String, String>),
+}
+
+/// Upgrades state from schema version `from_version` to `from_version + 1`.
+#[derive(Clone, Copy)]
+pub struct StateUpgrader {
+    pub from_version: i64,
+    /// Returns the JSON encoded state for the next version.
+    pub upgrade: fn(RawState) -> DResult<serde_json::Value>,
+}
+
+/// Upgrades `state` from `version` to the version of `schema` by running the upgraders for every version in between,
+/// and then decodes it using `schema`.
+pub fn upgrade_state_chain(
+    schema: &Schema,
+    upgraders: &[StateUpgrader],
+    version: i64,
+    state: RawState,
+) -> DResult<Value> {
+    let mut state = state;
+    for version in version..schema.version {
+        let Some(upgrader) = upgraders.iter().find(|u| u.from_version == version) else {
+            return Err(Diagnostic::error_string(format!(
+                "Cannot upgrade state from schema version {version} to {}, there is no upgrader for version {version}",
+                schema.version,
+            ))
+            .into());
+        };
+        state = RawState::Json((upgrader.upgrade)(state)?);
+    }
+
+    match state {
+        RawState::Json(json) => Value::json_unpack_value(&json, &schema.typ()),
+        RawState::Flatmap(_) => Err(Diagnostic::error_string(
+            "Cannot use state in the legacy flatmap format, it must be upgraded to JSON by a state upgrader",
+        )
+        .into()),
+    }
+}
+
+/// Imports a resource by setting its `id` attribute to the import ID and leaving all other attributes null.
+/// `read` is then responsible for fetching the rest of the state using the ID.
+/// Call this from [`Resource::import`] with `Self` as the resource.
+pub fn import_passthrough_id<R: Resource>(id: String) -> DResult<Value> {
+    let schema = R::schema();
+    match schema.attributes.get("id") {
+        Some(Attribute::String { .. }) => {}
+        Some(_) => {
+            return Err(Diagnostic::error_string(
+                "Resource cannot be imported by ID as its 'id' attribute is not a string",
+            )
+            .into())
+        }
+        None => {
+            return Err(Diagnostic::error_string(
+                "Resource cannot be imported by ID as it does not have an 'id' attribute",
+            )
+            .into())
+        }
+    }
+
+    let mut attrs = empty_object(&schema.attributes, &schema.blocks);
+    attrs.insert("id".to_owned(), Value::Known(ValueKind::String(id)));
+    Ok(Value::Known(ValueKind::Object(attrs)))
+}
+
+/// An object with all attributes null and all blocks absent.
+fn empty_object(
+    attributes: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+) -> BTreeMap<String, Value> {
+    let attrs = attributes.keys().map(|name| (name.clone(), Value::Null));
+    let blocks = blocks.iter().map(|(name, block)| {
+        let value = match block.nesting {
+            BlockNesting::Single => Value::Null,
+            BlockNesting::Group => Value::Known(ValueKind::Object(empty_object(
+                &block.attributes,
+                &block.blocks,
+            ))),
+            BlockNesting::List => Value::Known(ValueKind::List(Vec::new())),
+            BlockNesting::Set => Value::Known(ValueKind::Set(ValueSet::new())),
+            BlockNesting::Map => Value::Known(ValueKind::Map(BTreeMap::new())),
+        };
+        (name.clone(), value)
+    });
+    attrs.chain(blocks).collect()
 }
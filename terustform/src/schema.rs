@@ -1,11 +1,51 @@
 use std::collections::HashMap;
 
-use crate::Type;
+use crate::{
+    AttrPath, ConfigValidator, DefaultValue, Diagnostic, Diagnostics, PlanModifier, Type, Validator,
+};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Schema {
+    /// The version of the schema, which must be incremented whenever the schema of a resource changes
+    /// in a way that requires upgrading existing state. See [`crate::resource::Resource::upgrade_state`].
+    pub version: i64,
+    pub description: String,
+    pub attributes: HashMap<String, Attribute>,
+    pub blocks: HashMap<String, Block>,
+    /// Validators for constraints between multiple attributes, run during config validation.
+    pub validators: Vec<ConfigValidator>,
+}
+
+/// A nested block, written as `name { ... }` in the configuration.
+/// Prefer nested attributes for new schemas, blocks mostly exist for compatibility.
+#[derive(Clone)]
+pub struct Block {
     pub description: String,
+    pub nesting: BlockNesting,
+    /// The minimum number of blocks that must be present.
+    /// Only meaningful for `List` and `Set` blocks, `Single` blocks are required if this is `1`.
+    pub min_items: u64,
+    /// The maximum number of blocks that may be present, `0` means no limit.
+    /// Only meaningful for `List` and `Set` blocks.
+    pub max_items: u64,
     pub attributes: HashMap<String, Attribute>,
+    pub blocks: HashMap<String, Block>,
+}
+
+/// How the objects of a block are nested in their parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockNesting {
+    /// At most one block, null if absent.
+    Single,
+    /// At most one block, but the value is never null.
+    /// Absent blocks result in an object with all attributes null.
+    Group,
+    /// An ordered list of blocks.
+    List,
+    /// An unordered set of unique blocks.
+    Set,
+    /// Blocks with a label, `name "label" { ... }`, keyed by the label.
+    Map,
 }
 
 #[derive(Clone)]
@@ -14,20 +54,133 @@ pub enum Attribute {
         description: String,
         mode: Mode,
         sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
     },
     Int64 {
         description: String,
         mode: Mode,
         sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+    },
+    Float64 {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+    },
+    /// An arbitrary precision number.
+    Number {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+    },
+    Bool {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+    },
+    /// A value of any type, the concrete type is only known at runtime.
+    Dynamic {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+    },
+    /// An ordered list of elements of the type `elem`.
+    List {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        elem: Type,
+    },
+    /// An unordered set of unique elements of the type `elem`.
+    Set {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        elem: Type,
+    },
+    /// A map from strings to elements of the type `elem`.
+    Map {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        elem: Type,
     },
+    /// A single nested object with the attributes `attrs`.
     Object {
         description: String,
         mode: Mode,
         sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        attrs: HashMap<String, Attribute>,
+    },
+    /// An ordered list of nested objects with the attributes `attrs`.
+    ListNested {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        attrs: HashMap<String, Attribute>,
+    },
+    /// An unordered set of unique nested objects with the attributes `attrs`.
+    SetNested {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
+        attrs: HashMap<String, Attribute>,
+    },
+    /// A map from strings to nested objects with the attributes `attrs`.
+    MapNested {
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+        default: Option<DefaultValue>,
         attrs: HashMap<String, Attribute>,
     },
 }
 
+/// How the objects of a nested attribute are nested in their parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NestingMode {
+    Single,
+    List,
+    Set,
+    Map,
+}
+
 #[derive(Clone, Copy)]
 pub enum Mode {
     Required,
@@ -39,9 +192,120 @@ pub enum Mode {
 impl Attribute {
     pub fn mode(&self) -> Mode {
         match *self {
-            Self::Int64 { mode, .. } => mode,
-            Self::String { mode, .. } => mode,
-            Self::Object { mode, .. } => mode,
+            Self::String { mode, .. }
+            | Self::Int64 { mode, .. }
+            | Self::Float64 { mode, .. }
+            | Self::Number { mode, .. }
+            | Self::Bool { mode, .. }
+            | Self::Dynamic { mode, .. }
+            | Self::List { mode, .. }
+            | Self::Set { mode, .. }
+            | Self::Map { mode, .. }
+            | Self::Object { mode, .. }
+            | Self::ListNested { mode, .. }
+            | Self::SetNested { mode, .. }
+            | Self::MapNested { mode, .. } => mode,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::String { description, .. }
+            | Self::Int64 { description, .. }
+            | Self::Float64 { description, .. }
+            | Self::Number { description, .. }
+            | Self::Bool { description, .. }
+            | Self::Dynamic { description, .. }
+            | Self::List { description, .. }
+            | Self::Set { description, .. }
+            | Self::Map { description, .. }
+            | Self::Object { description, .. }
+            | Self::ListNested { description, .. }
+            | Self::SetNested { description, .. }
+            | Self::MapNested { description, .. } => description,
+        }
+    }
+
+    pub fn sensitive(&self) -> bool {
+        match *self {
+            Self::String { sensitive, .. }
+            | Self::Int64 { sensitive, .. }
+            | Self::Float64 { sensitive, .. }
+            | Self::Number { sensitive, .. }
+            | Self::Bool { sensitive, .. }
+            | Self::Dynamic { sensitive, .. }
+            | Self::List { sensitive, .. }
+            | Self::Set { sensitive, .. }
+            | Self::Map { sensitive, .. }
+            | Self::Object { sensitive, .. }
+            | Self::ListNested { sensitive, .. }
+            | Self::SetNested { sensitive, .. }
+            | Self::MapNested { sensitive, .. } => sensitive,
+        }
+    }
+
+    pub fn validators(&self) -> &[Validator] {
+        match self {
+            Self::String { validators, .. }
+            | Self::Int64 { validators, .. }
+            | Self::Float64 { validators, .. }
+            | Self::Number { validators, .. }
+            | Self::Bool { validators, .. }
+            | Self::Dynamic { validators, .. }
+            | Self::List { validators, .. }
+            | Self::Set { validators, .. }
+            | Self::Map { validators, .. }
+            | Self::Object { validators, .. }
+            | Self::ListNested { validators, .. }
+            | Self::SetNested { validators, .. }
+            | Self::MapNested { validators, .. } => validators,
+        }
+    }
+
+    pub fn plan_modifiers(&self) -> &[PlanModifier] {
+        match self {
+            Self::String { plan_modifiers, .. }
+            | Self::Int64 { plan_modifiers, .. }
+            | Self::Float64 { plan_modifiers, .. }
+            | Self::Number { plan_modifiers, .. }
+            | Self::Bool { plan_modifiers, .. }
+            | Self::Dynamic { plan_modifiers, .. }
+            | Self::List { plan_modifiers, .. }
+            | Self::Set { plan_modifiers, .. }
+            | Self::Map { plan_modifiers, .. }
+            | Self::Object { plan_modifiers, .. }
+            | Self::ListNested { plan_modifiers, .. }
+            | Self::SetNested { plan_modifiers, .. }
+            | Self::MapNested { plan_modifiers, .. } => plan_modifiers,
+        }
+    }
+
+    pub fn default(&self) -> Option<&DefaultValue> {
+        match self {
+            Self::String { default, .. }
+            | Self::Int64 { default, .. }
+            | Self::Float64 { default, .. }
+            | Self::Number { default, .. }
+            | Self::Bool { default, .. }
+            | Self::Dynamic { default, .. }
+            | Self::List { default, .. }
+            | Self::Set { default, .. }
+            | Self::Map { default, .. }
+            | Self::Object { default, .. }
+            | Self::ListNested { default, .. }
+            | Self::SetNested { default, .. }
+            | Self::MapNested { default, .. } => default.as_ref(),
+        }
+    }
+
+    /// Returns the nested attributes and their nesting mode if this is a nested attribute.
+    pub fn nested(&self) -> Option<(NestingMode, &HashMap<String, Attribute>)> {
+        match self {
+            Self::Object { attrs, .. } => Some((NestingMode::Single, attrs)),
+            Self::ListNested { attrs, .. } => Some((NestingMode::List, attrs)),
+            Self::SetNested { attrs, .. } => Some((NestingMode::Set, attrs)),
+            Self::MapNested { attrs, .. } => Some((NestingMode::Map, attrs)),
+            _ => None,
         }
     }
 }
@@ -60,26 +324,131 @@ impl Mode {
     }
 }
 
+/// A model that describes a whole schema, usually implemented with `#[derive(Schema)]`.
+pub trait SchemaModel {
+    fn schema() -> Schema;
+}
+
+/// A model type with a corresponding kind of attribute.
+/// `#[derive(Schema)]` builds the attributes through this trait, so a field type
+/// that doesn't match any attribute kind is a compile error.
+/// Derived schemas implement it as a nested [`Attribute::Object`].
+pub trait AttributeModel {
+    fn attribute(
+        description: String,
+        mode: Mode,
+        sensitive: bool,
+        validators: Vec<Validator>,
+        plan_modifiers: Vec<PlanModifier>,
+    ) -> Attribute;
+}
+
 impl Schema {
     pub fn typ(&self) -> Type {
-        attrs_typ(&self.attributes)
+        object_typ(&self.attributes, &self.blocks)
+    }
+
+    /// Checks the schema of `name` for mistakes that Terraform would reject, once when the provider starts.
+    pub(crate) fn check(&self, name: &str) -> Diagnostics {
+        let mut diags = Diagnostics::default();
+        check_object(
+            name,
+            &AttrPath::root(),
+            &self.attributes,
+            &self.blocks,
+            &mut diags,
+        );
+        diags
+    }
+}
+
+fn check_object(
+    name: &str,
+    path: &AttrPath,
+    attrs: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+    diags: &mut Diagnostics,
+) {
+    for (attr_name, attr) in attrs {
+        let path = path.append_attribute_name(attr_name.clone());
+        if attr.default().is_some() && !attr.mode().computed() {
+            diags.push(Diagnostic::error_string(format!(
+                "Attribute {path} of {name} has a default value but is not computed, which Terraform does not allow"
+            )));
+        }
+        if let Some((_, attrs)) = attr.nested() {
+            check_object(name, &path, attrs, &HashMap::new(), diags);
+        }
+    }
+    for (block_name, block) in blocks {
+        let path = path.append_attribute_name(block_name.clone());
+        check_object(name, &path, &block.attributes, &block.blocks, diags);
+    }
+}
+
+impl Block {
+    pub fn typ(&self) -> Type {
+        let object = object_typ(&self.attributes, &self.blocks);
+        match self.nesting {
+            BlockNesting::Single | BlockNesting::Group => object,
+            BlockNesting::List => Type::List {
+                elem: Box::new(object),
+            },
+            BlockNesting::Set => Type::Set {
+                elem: Box::new(object),
+            },
+            BlockNesting::Map => Type::Map {
+                elem: Box::new(object),
+            },
+        }
     }
 }
 
 impl Attribute {
     pub fn typ(&self) -> Type {
         match self {
-            Attribute::Int64 { .. } => Type::Number,
             Attribute::String { .. } => Type::String,
+            Attribute::Int64 { .. } | Attribute::Float64 { .. } | Attribute::Number { .. } => {
+                Type::Number
+            }
+            Attribute::Bool { .. } => Type::Bool,
+            Attribute::Dynamic { .. } => Type::Dynamic,
+            Attribute::List { elem, .. } => Type::List {
+                elem: Box::new(elem.clone()),
+            },
+            Attribute::Set { elem, .. } => Type::Set {
+                elem: Box::new(elem.clone()),
+            },
+            Attribute::Map { elem, .. } => Type::Map {
+                elem: Box::new(elem.clone()),
+            },
             Attribute::Object { attrs, .. } => attrs_typ(attrs),
+            Attribute::ListNested { attrs, .. } => Type::List {
+                elem: Box::new(attrs_typ(attrs)),
+            },
+            Attribute::SetNested { attrs, .. } => Type::Set {
+                elem: Box::new(attrs_typ(attrs)),
+            },
+            Attribute::MapNested { attrs, .. } => Type::Map {
+                elem: Box::new(attrs_typ(attrs)),
+            },
         }
     }
 }
 
 fn attrs_typ(attrs: &HashMap<String, Attribute>) -> Type {
+    object_typ(attrs, &HashMap::new())
+}
+
+fn object_typ(attrs: &HashMap<String, Attribute>, blocks: &HashMap<String, Block>) -> Type {
     let attr_tys = attrs
         .iter()
         .map(|(name, attr)| (name.clone(), attr.typ()))
+        .chain(
+            blocks
+                .iter()
+                .map(|(name, block)| (name.clone(), block.typ())),
+        )
         .collect();
 
     let optionals = attrs
@@ -98,3 +467,199 @@ fn attrs_typ(attrs: &HashMap<String, Attribute>) -> Type {
         optionals,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::{
+        AttrPath, Attribute, Block, BlockNesting, DefaultValue, Mode, Schema, SchemaModel,
+        StringValue, Type, Value, ValueKind,
+    };
+
+    #[test]
+    fn block_typ() {
+        let schema = Schema {
+            version: 0,
+            description: "".to_owned(),
+            attributes: crate::attrs! {
+                "name" => Attribute::String {
+                    description: "".to_owned(),
+                    mode: Mode::Optional,
+                    sensitive: false,
+                    validators: vec![],
+                    plan_modifiers: vec![],
+                    default: None,
+                },
+            },
+            blocks: crate::attrs! {
+                "rule" => Block {
+                    description: "".to_owned(),
+                    nesting: BlockNesting::List,
+                    min_items: 0,
+                    max_items: 0,
+                    attributes: crate::attrs! {
+                        "port" => Attribute::Int64 {
+                            description: "".to_owned(),
+                            mode: Mode::Required,
+                            sensitive: false,
+                            validators: vec![],
+                            plan_modifiers: vec![],
+                            default: None,
+                        },
+                    },
+                    blocks: HashMap::new(),
+                },
+            },
+            validators: vec![],
+        };
+
+        assert_eq!(
+            schema.typ(),
+            Type::Object {
+                attrs: BTreeMap::from([
+                    ("name".to_owned(), Type::String),
+                    (
+                        "rule".to_owned(),
+                        Type::List {
+                            elem: Box::new(Type::Object {
+                                attrs: BTreeMap::from([("port".to_owned(), Type::Number)]),
+                                optionals: vec![],
+                            }),
+                        },
+                    ),
+                ]),
+                optionals: vec!["name".to_owned()],
+            }
+        );
+    }
+
+    /// A cat.
+    #[derive(crate::Schema)]
+    #[terustform(version = 2)]
+    #[allow(dead_code)]
+    struct Cat {
+        /// The name of the cat.
+        #[terustform(required)]
+        name: StringValue,
+        #[terustform(description = "What the cat says", computed, sensitive)]
+        meow: StringValue,
+        #[terustform(optional, computed)]
+        paws: Paws,
+        #[terustform(rename = "type")]
+        kind: StringValue,
+        #[terustform(flatten)]
+        common: Paws,
+        #[terustform(skip)]
+        cache: Option<String>,
+        mood: Mood,
+        past_moods: Vec<Mood>,
+    }
+
+    #[derive(crate::Model)]
+    #[allow(dead_code)]
+    enum Mood {
+        Happy,
+        Grumpy,
+    }
+
+    #[derive(crate::Schema)]
+    #[allow(dead_code)]
+    struct Paws {
+        left: StringValue,
+    }
+
+    #[test]
+    fn derive_schema() {
+        let schema = Cat::schema();
+        assert_eq!(schema.version, 2);
+        assert_eq!(schema.description, "A cat.");
+
+        let name = &schema.attributes["name"];
+        assert!(matches!(name, Attribute::String { .. }));
+        assert_eq!(name.description(), "The name of the cat.");
+        assert!(name.mode().required());
+        assert!(!name.sensitive());
+
+        let meow = &schema.attributes["meow"];
+        assert_eq!(meow.description(), "What the cat says");
+        assert!(matches!(meow.mode(), Mode::Computed));
+        assert!(meow.sensitive());
+
+        let paws = &schema.attributes["paws"];
+        assert!(matches!(paws.mode(), Mode::OptionalComputed));
+        let Attribute::Object { attrs, .. } = paws else {
+            panic!("paws must be an object");
+        };
+        assert!(matches!(attrs["left"].mode(), Mode::Optional));
+
+        let mut names = schema.attributes.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            ["left", "meow", "mood", "name", "past_moods", "paws", "type"]
+        );
+
+        let mood = &schema.attributes["mood"];
+        assert!(matches!(mood, Attribute::String { .. }));
+        assert_eq!(
+            mood.validators()[0].description(),
+            "value must be one of: 'Happy', 'Grumpy'"
+        );
+
+        let past_moods = &schema.attributes["past_moods"];
+        assert!(matches!(past_moods, Attribute::List { .. }));
+        assert_eq!(
+            past_moods.validators()[0].description(),
+            "every element: value must be one of: 'Happy', 'Grumpy'"
+        );
+        let list = ValueKind::List(vec![
+            Value::Known(ValueKind::String("Happy".to_owned())),
+            Value::Known(ValueKind::String("Sleepy".to_owned())),
+        ]);
+        let errs = past_moods.validators()[0]
+            .validate(&list, &AttrPath::attr("past_moods"))
+            .unwrap_err();
+        let diag = errs.iter().next().unwrap();
+        assert_eq!(diag.attr.as_ref().unwrap().to_string(), "past_moods[1]");
+    }
+
+    #[test]
+    fn default_not_computed() {
+        let attr = |mode| Attribute::String {
+            description: "".to_owned(),
+            mode,
+            sensitive: false,
+            validators: vec![],
+            plan_modifiers: vec![],
+            default: Some(DefaultValue::string("meow")),
+        };
+        let schema = Schema {
+            version: 0,
+            description: "".to_owned(),
+            attributes: crate::attrs! {
+                "sound" => attr(Mode::OptionalComputed),
+            },
+            blocks: crate::attrs! {
+                "paw" => Block {
+                    description: "".to_owned(),
+                    nesting: BlockNesting::Single,
+                    min_items: 0,
+                    max_items: 0,
+                    attributes: crate::attrs! {
+                        "claw" => attr(Mode::Optional),
+                    },
+                    blocks: HashMap::new(),
+                },
+            },
+            validators: vec![],
+        };
+
+        let diags = schema.check("resource cat");
+        let msgs = diags.iter().map(|diag| &diag.msg).collect::<Vec<_>>();
+        assert_eq!(
+            msgs,
+            ["Attribute paw.claw of resource cat has a default value but is not computed, which Terraform does not allow"]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Panics in provider code must not take down the whole plugin, as Terraform would only report
+//! that the plugin crashed. Instead, we catch them and turn them into diagnostics.
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::RefCell,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Once,
+    task::{Context, Poll},
+};
+
+use crate::{DResult, Diagnostic, Diagnostics};
+
+thread_local! {
+    /// The backtrace of the last panic on this thread, captured by the panic hook.
+    static LAST_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, converting a panic into an error diagnostic.
+pub(super) fn catch_unwind_sync<T>(f: impl FnOnce() -> DResult<T>) -> DResult<T> {
+    install_hook();
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(panic_diag(payload)))
+}
+
+/// Runs `fut`, converting a panic while polling it into an error diagnostic.
+pub(super) async fn catch_unwind<T>(fut: impl Future<Output = DResult<T>>) -> DResult<T> {
+    install_hook();
+    CatchUnwind(Box::pin(fut))
+        .await
+        .unwrap_or_else(|payload| Err(panic_diag(payload)))
+}
+
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let fut = self.0.as_mut();
+        match panic::catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Wraps the current panic hook to also remember the backtrace, which isn't part of the panic payload.
+fn install_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            LAST_BACKTRACE.with(|bt| *bt.borrow_mut() = Some(Backtrace::force_capture()));
+            prev(info);
+        }));
+    });
+}
+
+fn panic_diag(payload: Box<dyn Any + Send>) -> Diagnostics {
+    let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    let backtrace = LAST_BACKTRACE
+        .with(|bt| bt.borrow_mut().take())
+        .map(|bt| bt.to_string())
+        .unwrap_or_default();
+
+    Diagnostic::error(
+        "The provider panicked",
+        format!("This is a bug in the provider, please report it.\n\npanic: {msg}\n\n{backtrace}"),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{catch_unwind, catch_unwind_sync};
+    use crate::DResult;
+
+    #[test]
+    fn sync_panic() {
+        let errs = catch_unwind_sync::<()>(|| panic!("meow")).unwrap_err();
+        let diag = errs.iter().next().unwrap();
+        assert!(diag.detail.contains("panic: meow"));
+    }
+
+    #[tokio::test]
+    async fn async_panic() {
+        let ok: DResult<u8> = catch_unwind(async { Ok(1) }).await;
+        assert_eq!(ok.unwrap(), 1);
+
+        let errs = catch_unwind::<()>(async {
+            tokio::task::yield_now().await;
+            panic!("{}", "woof")
+        })
+        .await
+        .unwrap_err();
+        let diag = errs.iter().next().unwrap();
+        assert!(diag.detail.contains("panic: woof"));
+    }
+}
@@ -1,11 +1,14 @@
-use crate::{AttrPath, AttrPathSegment, Attribute, Diagnostics, Mode, Schema, Value};
+use crate::{
+    AttrPath, AttrPathSegment, Attribute, Block, BlockNesting, Diagnostics, NestingMode, Schema,
+    Severity, Type, Value,
+};
 
 use super::grpc::tfplugin6;
 
 impl Schema {
     pub(crate) fn into_tfplugin(self) -> tfplugin6::Schema {
         tfplugin6::Schema {
-            version: 1,
+            version: self.version,
             block: Some(tfplugin6::schema::Block {
                 version: 0,
                 attributes: self
@@ -13,7 +16,11 @@ impl Schema {
                     .into_iter()
                     .map(|(name, attr)| attr.into_tfplugin(name))
                     .collect(),
-                block_types: vec![],
+                block_types: self
+                    .blocks
+                    .into_iter()
+                    .map(|(name, block)| block.into_tfplugin(name))
+                    .collect(),
                 description: self.description,
                 description_kind: tfplugin6::StringKind::Markdown as _,
                 deprecated: false,
@@ -22,61 +29,83 @@ impl Schema {
     }
 }
 
-impl Attribute {
-    pub(crate) fn into_tfplugin(self, name: String) -> tfplugin6::schema::Attribute {
-        let mut attr = tfplugin6::schema::Attribute {
-            name,
-            r#type: vec![],
-            nested_type: None,
-            description: "<placeholder, this is a bug in terustform>".to_owned(),
-            required: false,
-            optional: false,
-            computed: true,
-            sensitive: false,
-            description_kind: tfplugin6::StringKind::Markdown as _,
-            deprecated: false,
-        };
+impl Block {
+    pub(crate) fn into_tfplugin(self, name: String) -> tfplugin6::schema::NestedBlock {
+        use tfplugin6::schema::nested_block::NestingMode;
 
-        let set_modes = |attr: &mut tfplugin6::schema::Attribute, mode: Mode| {
-            attr.required = mode.required();
-            attr.optional = mode.optional();
-            attr.computed = mode.computed();
+        let nesting = match self.nesting {
+            BlockNesting::Single => NestingMode::Single,
+            BlockNesting::Group => NestingMode::Group,
+            BlockNesting::List => NestingMode::List,
+            BlockNesting::Set => NestingMode::Set,
+            BlockNesting::Map => NestingMode::Map,
         };
 
-        attr.r#type = self.typ().to_json().into_bytes();
+        tfplugin6::schema::NestedBlock {
+            type_name: name,
+            block: Some(tfplugin6::schema::Block {
+                version: 0,
+                attributes: self
+                    .attributes
+                    .into_iter()
+                    .map(|(name, attr)| attr.into_tfplugin(name))
+                    .collect(),
+                block_types: self
+                    .blocks
+                    .into_iter()
+                    .map(|(name, block)| block.into_tfplugin(name))
+                    .collect(),
+                description: self.description,
+                description_kind: tfplugin6::StringKind::Markdown as _,
+                deprecated: false,
+            }),
+            nesting: nesting as _,
+            min_items: self.min_items.try_into().unwrap_or(i64::MAX),
+            max_items: self.max_items.try_into().unwrap_or(i64::MAX),
+        }
+    }
+}
+
+impl Attribute {
+    pub(crate) fn into_tfplugin(self, name: String) -> tfplugin6::schema::Attribute {
+        let mode = self.mode();
 
-        match self {
-            Attribute::String {
-                description,
-                mode,
-                sensitive,
-            } => {
-                attr.description = description;
-                set_modes(&mut attr, mode);
-                attr.sensitive = sensitive;
-            }
-            Attribute::Int64 {
-                description,
-                mode,
-                sensitive,
-            } => {
-                attr.description = description;
-                set_modes(&mut attr, mode);
-                attr.sensitive = sensitive;
-            }
-            Attribute::Object {
-                description,
-                mode,
-                sensitive,
-                attrs: _,
-            } => {
-                attr.description = description;
-                set_modes(&mut attr, mode);
-                attr.sensitive = sensitive;
+        // Nested attributes get their type from `nested_type`, so `type` must be empty.
+        let (r#type, nested_type) = match self.nested() {
+            Some((nesting, attrs)) => {
+                let nesting = match nesting {
+                    NestingMode::Single => tfplugin6::schema::object::NestingMode::Single,
+                    NestingMode::List => tfplugin6::schema::object::NestingMode::List,
+                    NestingMode::Set => tfplugin6::schema::object::NestingMode::Set,
+                    NestingMode::Map => tfplugin6::schema::object::NestingMode::Map,
+                };
+                #[allow(deprecated)]
+                let object = tfplugin6::schema::Object {
+                    attributes: attrs
+                        .iter()
+                        .map(|(name, attr)| attr.clone().into_tfplugin(name.clone()))
+                        .collect(),
+                    nesting: nesting as _,
+                    min_items: 0,
+                    max_items: 0,
+                };
+                (vec![], Some(object))
             }
-        }
+            None => (self.typ().to_json().into_bytes(), None),
+        };
 
-        attr
+        tfplugin6::schema::Attribute {
+            name,
+            r#type,
+            nested_type,
+            description: self.description().to_owned(),
+            required: mode.required(),
+            optional: mode.optional(),
+            computed: mode.computed(),
+            sensitive: self.sensitive(),
+            description_kind: tfplugin6::StringKind::Markdown as _,
+            deprecated: false,
+        }
     }
 }
 
@@ -84,11 +113,14 @@ impl Diagnostics {
     pub(crate) fn into_tfplugin_diags(self) -> Vec<tfplugin6::Diagnostic> {
         self.diags
             .into_iter()
-            .map(|err| tfplugin6::Diagnostic {
-                severity: tfplugin6::diagnostic::Severity::Error as _,
-                summary: err.msg,
-                detail: "".to_owned(),
-                attribute: err.attr.map(|path| path.into_tfplugin()),
+            .map(|diag| tfplugin6::Diagnostic {
+                severity: match diag.severity {
+                    Severity::Error => tfplugin6::diagnostic::Severity::Error as _,
+                    Severity::Warning => tfplugin6::diagnostic::Severity::Warning as _,
+                },
+                summary: diag.msg,
+                detail: diag.detail,
+                attribute: diag.attr.map(|path| path.into_tfplugin()),
             })
             .collect()
     }
@@ -119,10 +151,21 @@ impl AttrPath {
 }
 
 impl Value {
-    pub(crate) fn into_tfplugin(self) -> Option<tfplugin6::DynamicValue> {
-        Some(tfplugin6::DynamicValue {
-            msgpack: self.msg_pack(),
-            json: vec![],
-        })
+    /// Encodes the value as msgpack. Encoding errors are added to `diags`.
+    pub(crate) fn into_tfplugin(
+        self,
+        typ: &Type,
+        diags: &mut Diagnostics,
+    ) -> Option<tfplugin6::DynamicValue> {
+        match self.msg_pack(typ) {
+            Ok(msgpack) => Some(tfplugin6::DynamicValue {
+                msgpack,
+                json: vec![],
+            }),
+            Err(errs) => {
+                diags.extend(errs);
+                None
+            }
+        }
     }
 }
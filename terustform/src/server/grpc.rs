@@ -1,6 +1,5 @@
 #![allow(unused_variables, unused_imports)]
 
-
 #[allow(warnings)]
 pub mod tfplugin6 {
     tonic::include_proto!("tfplugin6");
@@ -24,20 +23,6 @@ use tracing::info;
 
 use crate::values::Type;
 
-fn empty_schema() -> tfplugin6::Schema {
-    tfplugin6::Schema {
-        version: 1,
-        block: Some(tfplugin6::schema::Block {
-            version: 0,
-            attributes: vec![],
-            block_types: vec![],
-            description: "hello world".to_owned(),
-            description_kind: 0,
-            deprecated: false,
-        }),
-    }
-}
-
 #[tonic::async_trait]
 impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
     /// GetMetadata returns upfront information about server capabilities and
@@ -69,8 +54,8 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         let schemas = self.do_get_provider_schema().await;
 
         let reply = tfplugin6::get_provider_schema::Response {
-            provider: Some(empty_schema()),
-            provider_meta: Some(empty_schema()),
+            provider: Some(schemas.provider),
+            provider_meta: Some(schemas.provider_meta),
             server_capabilities: Some(tfplugin6::ServerCapabilities {
                 plan_destroy: true,
                 get_provider_schema_optional: true,
@@ -92,10 +77,11 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         request: Request<tfplugin6::validate_provider_config::Request>,
     ) -> Result<Response<tfplugin6::validate_provider_config::Response>, Status> {
         tracing::info!("validate_provider_config");
+        let req = request.get_ref();
 
-        let reply = tfplugin6::validate_provider_config::Response {
-            diagnostics: vec![],
-        };
+        let diagnostics = self.do_validate_provider_config(&req.config).await;
+
+        let reply = tfplugin6::validate_provider_config::Response { diagnostics };
 
         Ok(Response::new(reply))
     }
@@ -107,12 +93,13 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         request: Request<tfplugin6::validate_resource_config::Request>,
     ) -> Result<Response<tfplugin6::validate_resource_config::Response>, Status> {
         tracing::info!(name=?request.get_ref().type_name, "validate_resource_config");
+        let req = request.get_ref();
 
-        // No validators for now.
+        let diagnostics = self
+            .do_validate_resource_config(&req.type_name, &req.config)
+            .await;
 
-        let reply = tfplugin6::validate_resource_config::Response {
-            diagnostics: vec![],
-        };
+        let reply = tfplugin6::validate_resource_config::Response { diagnostics };
 
         Ok(Response::new(reply))
     }
@@ -124,12 +111,13 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         request: Request<tfplugin6::validate_data_resource_config::Request>,
     ) -> Result<Response<tfplugin6::validate_data_resource_config::Response>, Status> {
         tracing::info!(name=?request.get_ref().type_name, "validate_data_resource_config");
+        let req = request.get_ref();
 
-        // No validators for now.
+        let diagnostics = self
+            .do_validate_data_resource_config(&req.type_name, &req.config)
+            .await;
 
-        let reply = tfplugin6::validate_data_resource_config::Response {
-            diagnostics: vec![],
-        };
+        let reply = tfplugin6::validate_data_resource_config::Response { diagnostics };
 
         Ok(Response::new(reply))
     }
@@ -142,10 +130,15 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         request: Request<tfplugin6::upgrade_resource_state::Request>,
     ) -> Result<Response<tfplugin6::upgrade_resource_state::Response>, Status> {
         tracing::info!(name=?request.get_ref().type_name, "upgrade_resource_state");
-        // We don't do anything interesting, it's fine.
+        let req = request.get_ref();
+
+        let (upgraded_state, diagnostics) = self
+            .do_upgrade_resource_state(&req.type_name, req.version, &req.raw_state)
+            .await;
+
         let reply = tfplugin6::upgrade_resource_state::Response {
-            upgraded_state: None,
-            diagnostics: vec![],
+            upgraded_state,
+            diagnostics,
         };
 
         Ok(Response::new(reply))
@@ -172,12 +165,12 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         let req = request.get_ref();
 
         let (new_state, diagnostics) = self
-            .do_read_resource(&req.type_name, &req.current_state)
+            .do_read_resource(&req.type_name, &req.current_state, &req.provider_meta)
             .await;
 
         let reply = tfplugin6::read_resource::Response {
             deferred: None,
-            diagnostics: vec![],
+            diagnostics,
             new_state,
             private: vec![],
         };
@@ -194,20 +187,18 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         tracing::info!(name=?request.get_ref().type_name, "plan_resource_change");
         let req = request.get_ref();
 
-        // We don't do anything interesting like requires_replace for now.
-        // We're supposed to handle defaults here.
-
-        let (planned_state, diagnostics) = self
+        let (planned_state, requires_replace, diagnostics) = self
             .do_plan_resource_change(
                 &req.type_name,
                 &req.prior_state,
                 &req.proposed_new_state,
                 &req.config,
+                &req.provider_meta,
             )
             .await;
         let reply = tfplugin6::plan_resource_change::Response {
             planned_state,
-            requires_replace: vec![],
+            requires_replace,
             planned_private: vec![],
             diagnostics,
             legacy_type_system: false,
@@ -233,6 +224,7 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
                 &req.prior_state,
                 &req.planned_state,
                 &req.config,
+                &req.provider_meta,
             )
             .await;
         tracing::debug!(?new_state, ?diagnostics, "post apply_resource_change");
@@ -253,9 +245,19 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         &self,
         request: Request<tfplugin6::import_resource_state::Request>,
     ) -> Result<Response<tfplugin6::import_resource_state::Response>, Status> {
-        tracing::error!(name=?request.get_ref().type_name, "import_resource_state");
+        tracing::info!(name=?request.get_ref().type_name, "import_resource_state");
+        let req = request.into_inner();
+
+        let (imported_resources, diagnostics) =
+            self.do_import_resource_state(&req.type_name, req.id).await;
 
-        Err(Status::unimplemented("import_resource_state"))
+        let reply = tfplugin6::import_resource_state::Response {
+            imported_resources,
+            diagnostics,
+            deferred: None,
+        };
+
+        Ok(Response::new(reply))
     }
 
     #[tracing::instrument(skip(self, request), fields(source_name = request.get_ref().source_type_name))]
@@ -277,7 +279,9 @@ impl<P: crate::provider::Provider> Provider for super::ProviderHandler<P> {
         tracing::info!(name=?request.get_ref().type_name, "read_data_source");
         let req = request.get_ref();
 
-        let (state, diagnostics) = self.do_read_data_source(&req.type_name, &req.config).await;
+        let (state, diagnostics) = self
+            .do_read_data_source(&req.type_name, &req.config, &req.provider_meta)
+            .await;
 
         let reply = tfplugin6::read_data_source::Response {
             state,
@@ -5,21 +5,31 @@ use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
 use crate::{
-    provider::{MkDataSource, MkResource, Provider, StoredDataSource, StoredResource},
-    Attribute, DResult, Diagnostic, Diagnostics, Type, Value, ValueKind,
+    provider::{
+        MkDataSource, MkResource, Provider, StoredDataSource, StoredResource, ValidateConfigFn,
+    },
+    resource::{upgrade_state_chain, RawState, ResourcePlan, StateUpgrader},
+    AttrPath, DResult, Diagnostic, Diagnostics, Schema, Type, Value,
 };
 
-use super::{grpc::tfplugin6, Schemas};
+use super::{
+    catch_unwind::{catch_unwind, catch_unwind_sync},
+    grpc::tfplugin6,
+    Schemas,
+};
 
 pub struct ProviderHandler<P: Provider> {
     pub(super) shutdown: CancellationToken,
+    provider: P,
+    provider_schema: Schema,
+    /// The schema of `provider_meta`, empty if the provider doesn't have one.
+    provider_meta_schema: Schema,
     /// Delayed diagnostics reporting in `GetProviderSchema` for better UX.
     state: Mutex<ProviderState<P>>,
 }
 
 enum ProviderState<P: Provider> {
     Setup {
-        provider: P,
         mk_ds: HashMap<String, MkDataSource<P::Data>>,
         mk_rs: HashMap<String, MkResource<P::Data>>,
     },
@@ -41,10 +51,15 @@ impl<P: Provider> ProviderHandler<P> {
     pub fn new(shutdown: CancellationToken, provider: P) -> Self {
         let mut errors = Diagnostics::default();
         let name = provider.name();
+        let provider_schema = provider.schema();
+        let provider_meta_schema = provider.meta_schema().unwrap_or_default();
+        errors.extend(provider_schema.check(&format!("provider {name}")));
+        errors.extend(provider_meta_schema.check(&format!("provider_meta of {name}")));
 
         let mut mk_ds = HashMap::new();
         for ds in provider.data_sources() {
             let ds_name = (ds.name)(&name);
+            errors.extend(ds.schema.check(&format!("data source {ds_name}")));
             let entry = mk_ds.insert(ds_name.clone(), ds);
             if entry.is_some() {
                 errors.push(Diagnostic::error_string(format!(
@@ -56,6 +71,7 @@ impl<P: Provider> ProviderHandler<P> {
         let mut mk_rs = HashMap::new();
         for rs in provider.resources() {
             let rs_name = (rs.name)(&name);
+            errors.extend(rs.schema.check(&format!("resource {rs_name}")));
             let entry = mk_rs.insert(rs_name.clone(), rs);
             if entry.is_some() {
                 errors.push(Diagnostic::error_string(format!(
@@ -67,14 +83,13 @@ impl<P: Provider> ProviderHandler<P> {
         let state = if errors.has_errors() {
             ProviderState::Failed { diags: errors }
         } else {
-            ProviderState::Setup {
-                provider,
-                mk_ds,
-                mk_rs,
-            }
+            ProviderState::Setup { mk_ds, mk_rs }
         };
         Self {
             shutdown,
+            provider,
+            provider_schema,
+            provider_meta_schema,
             state: Mutex::new(state),
         }
     }
@@ -84,23 +99,31 @@ impl<P: Provider> ProviderHandler<P> {
         config: &Option<tfplugin6::DynamicValue>,
     ) -> (Option<()>, Vec<tfplugin6::Diagnostic>) {
         let mut state = self.state.lock().await;
-        let (provider, mk_ds, mk_rs) = match &*state {
-            ProviderState::Setup {
-                provider,
-                mk_ds,
-                mk_rs,
-            } => (provider, mk_ds, mk_rs),
+        let (mk_ds, mk_rs) = match &*state {
+            ProviderState::Setup { mk_ds, mk_rs } => (mk_ds, mk_rs),
             ProviderState::Failed { diags } => return (None, diags.clone().into_tfplugin_diags()),
-            ProviderState::Configured { .. } => unreachable!("called configure twice"),
+            ProviderState::Configured { .. } => {
+                return (
+                    None,
+                    Diagnostics::from(Diagnostic::error_string(
+                        "The provider has already been configured",
+                    ))
+                    .into_tfplugin_diags(),
+                )
+            }
         };
-        let config = tf_try!(parse_dynamic_value(config, &provider.schema().typ()));
+        let config = tf_try!(parse_dynamic_value(config, &self.provider_schema.typ()));
 
-        let data = tf_try!(provider.configure(config).await);
-        let mut diags = vec![];
+        let mut user_diags = Diagnostics::default();
+        let data = tf_try!(
+            catch_unwind(self.provider.configure(config, &mut user_diags)).await,
+            user_diags
+        );
+        let mut diags = user_diags.into_tfplugin_diags();
 
         let mut data_sources = HashMap::new();
         for (ds_name, ds) in mk_ds {
-            let ds = (ds.mk)(data.clone());
+            let ds = catch_unwind_sync(|| (ds.mk)(data.clone()));
 
             match ds {
                 Ok(ds) => {
@@ -112,7 +135,7 @@ impl<P: Provider> ProviderHandler<P> {
 
         let mut resources = HashMap::new();
         for (rs_name, rs) in mk_rs {
-            let rs = (rs.mk)(data.clone());
+            let rs = catch_unwind_sync(|| (rs.mk)(data.clone()));
 
             match rs {
                 Ok(rs) => {
@@ -133,93 +156,135 @@ impl<P: Provider> ProviderHandler<P> {
     pub(super) async fn do_get_provider_schema(&self) -> Schemas {
         let state = self.state.lock().await;
 
-        let (mk_ds, mk_rs) = match &*state {
-            ProviderState::Setup {
-                mk_ds,
-                mk_rs,
-                provider: _,
-            } => (mk_ds, mk_rs),
+        let (data_sources, resources) = match &*state {
+            ProviderState::Setup { mk_ds, mk_rs } => (
+                schemas(mk_ds.iter().map(|(name, ds)| (name, &ds.schema))),
+                schemas(mk_rs.iter().map(|(name, rs)| (name, &rs.schema))),
+            ),
             ProviderState::Failed { diags } => {
                 return Schemas {
+                    provider: self.provider_schema.clone().into_tfplugin(),
+                    provider_meta: self.provider_meta_schema.clone().into_tfplugin(),
                     resources: HashMap::new(),
                     data_sources: HashMap::new(),
                     diagnostics: diags.clone().into_tfplugin_diags(),
                 }
             }
-            ProviderState::Configured { .. } => {
-                unreachable!("called get_schemas after configuration")
-            }
+            // Terraform may ask for the schema again after configuring the provider.
+            ProviderState::Configured {
+                data_sources,
+                resources,
+            } => (
+                schemas(data_sources.iter().map(|(name, ds)| (name, &ds.schema))),
+                schemas(resources.iter().map(|(name, rs)| (name, &rs.schema))),
+            ),
         };
-        let data_sources = mk_ds
-            .iter()
-            .map(|(name, ds)| {
-                tracing::debug!(?name, "Initializing data source");
-                (name.to_owned(), ds.schema.clone().into_tfplugin())
-            })
-            .collect::<HashMap<String, tfplugin6::Schema>>();
-        let resources = mk_rs
-            .iter()
-            .map(|(name, ds)| {
-                tracing::debug!(?name, "Initializing resources");
-                (name.to_owned(), ds.schema.clone().into_tfplugin())
-            })
-            .collect::<HashMap<String, tfplugin6::Schema>>();
 
         Schemas {
+            provider: self.provider_schema.clone().into_tfplugin(),
+            provider_meta: self.provider_meta_schema.clone().into_tfplugin(),
             resources,
             data_sources,
             diagnostics: TF_OK,
         }
     }
 
+    pub(super) async fn do_validate_provider_config(
+        &self,
+        config: &Option<tfplugin6::DynamicValue>,
+    ) -> Vec<tfplugin6::Diagnostic> {
+        let config = match parse_dynamic_value(config, &self.provider_schema.typ()) {
+            Ok(config) => config,
+            Err(errs) => return errs.into_tfplugin_diags(),
+        };
+
+        // Validators of the schema are user code too.
+        let mut diags = catch_unwind_sync(|| Ok(self.provider_schema.validate(&config)))
+            .unwrap_or_else(|errs| errs);
+        if let Err(errs) = catch_unwind_sync(|| self.provider.validate_config(&config, &mut diags))
+        {
+            diags.extend(errs);
+        }
+        diags.into_tfplugin_diags()
+    }
+
+    pub(super) async fn do_validate_resource_config(
+        &self,
+        type_name: &str,
+        config: &Option<tfplugin6::DynamicValue>,
+    ) -> Vec<tfplugin6::Diagnostic> {
+        // Validation happens before the provider is configured, but may also happen after.
+        let (schema, validate_config, _, _) = match self.get_resource_schema(type_name).await {
+            Ok(schema) => schema,
+            Err(errs) => return errs.into_tfplugin_diags(),
+        };
+
+        let config = match parse_dynamic_value(config, &schema.typ()) {
+            Ok(config) => config,
+            Err(errs) => return errs.into_tfplugin_diags(),
+        };
+
+        let mut diags =
+            catch_unwind_sync(|| Ok(schema.validate(&config))).unwrap_or_else(|errs| errs);
+        if let Err(errs) = catch_unwind_sync(|| validate_config(&config, &mut diags)) {
+            diags.extend(errs);
+        }
+        diags.into_tfplugin_diags()
+    }
+
+    pub(super) async fn do_validate_data_resource_config(
+        &self,
+        type_name: &str,
+        config: &Option<tfplugin6::DynamicValue>,
+    ) -> Vec<tfplugin6::Diagnostic> {
+        let (schema, validate_config) = match self.get_data_source_schema(type_name).await {
+            Ok(schema) => schema,
+            Err(errs) => return errs.into_tfplugin_diags(),
+        };
+
+        let config = match parse_dynamic_value(config, &schema.typ()) {
+            Ok(config) => config,
+            Err(errs) => return errs.into_tfplugin_diags(),
+        };
+
+        let mut diags =
+            catch_unwind_sync(|| Ok(schema.validate(&config))).unwrap_or_else(|errs| errs);
+        if let Err(errs) = catch_unwind_sync(|| validate_config(&config, &mut diags)) {
+            diags.extend(errs);
+        }
+        diags.into_tfplugin_diags()
+    }
+
     pub(super) async fn do_read_data_source(
         &self,
         type_name: &str,
         config: &Option<tfplugin6::DynamicValue>,
+        provider_meta: &Option<tfplugin6::DynamicValue>,
     ) -> (Option<tfplugin6::DynamicValue>, Vec<tfplugin6::Diagnostic>) {
-        let ds: StoredDataSource = {
-            let state = self.state.lock().await;
-            match &*state {
-                ProviderState::Setup { .. } => {
-                    unreachable!("must be set up before calling data sources")
-                }
-                ProviderState::Failed { diags } => {
-                    return (None, diags.clone().into_tfplugin_diags())
-                }
-                ProviderState::Configured {
-                    data_sources,
-                    resources: _,
-                } => data_sources.get(type_name).unwrap().clone(),
-            }
-        };
+        let ds = tf_try!(self.get_data_source(type_name).await);
 
         let typ = ds.schema.typ();
         let config = tf_try!(parse_dynamic_value(config, &typ));
-        let state = tf_try!(ds.ds.read(config).await);
+        let provider_meta = tf_try!(self.parse_provider_meta(provider_meta));
+        let mut diags = Diagnostics::default();
+        let state = tf_try!(
+            catch_unwind(ds.ds.read(config, provider_meta, &mut diags)).await,
+            diags
+        );
 
-        (state.into_tfplugin(), TF_OK)
+        (
+            state.into_tfplugin(&typ, &mut diags),
+            diags.into_tfplugin_diags(),
+        )
     }
 
     pub(super) async fn do_read_resource(
         &self,
         type_name: &str,
         current_state: &Option<tfplugin6::DynamicValue>,
+        provider_meta: &Option<tfplugin6::DynamicValue>,
     ) -> (Option<tfplugin6::DynamicValue>, Vec<tfplugin6::Diagnostic>) {
-        let rs: StoredResource = {
-            let state = self.state.lock().await;
-            match &*state {
-                ProviderState::Setup { .. } => {
-                    unreachable!("must be set up before calling data sources")
-                }
-                ProviderState::Failed { diags } => {
-                    return (None, diags.clone().into_tfplugin_diags())
-                }
-                ProviderState::Configured {
-                    data_sources: _,
-                    resources,
-                } => resources.get(type_name).unwrap().clone(),
-            }
-        };
+        let rs = tf_try!(self.get_resource(type_name).await);
 
         let typ = rs.schema.typ();
         let current_state = tf_try!(parse_dynamic_value(current_state, &typ));
@@ -228,9 +293,22 @@ impl<P: Provider> ProviderHandler<P> {
             return (None, TF_OK);
         }
 
-        let new_state = tf_try!(rs.rs.read(current_state).await);
+        let provider_meta = tf_try!(self.parse_provider_meta(provider_meta));
+        let mut diags = Diagnostics::default();
+        let new_state = tf_try!(
+            catch_unwind(rs.rs.read(current_state, provider_meta, &mut diags)).await,
+            diags
+        );
+        // A null state tells Terraform that the resource is gone.
+        let new_state = new_state.unwrap_or_else(|| {
+            info!("resource no longer exists, removing it from state");
+            Value::Null
+        });
 
-        (new_state.into_tfplugin(), TF_OK)
+        (
+            new_state.into_tfplugin(&typ, &mut diags),
+            diags.into_tfplugin_diags(),
+        )
     }
 
     pub(super) async fn do_plan_resource_change(
@@ -239,62 +317,45 @@ impl<P: Provider> ProviderHandler<P> {
         prior_state: &Option<tfplugin6::DynamicValue>,
         proposed_new_state: &Option<tfplugin6::DynamicValue>,
         config: &Option<tfplugin6::DynamicValue>,
-    ) -> (Option<tfplugin6::DynamicValue>, Vec<tfplugin6::Diagnostic>) {
-        let rs: StoredResource = {
-            let state = self.state.lock().await;
-            match &*state {
-                ProviderState::Setup { .. } => {
-                    unreachable!("must be set up before calling data sources")
-                }
-                ProviderState::Failed { diags } => {
-                    return (None, diags.clone().into_tfplugin_diags())
-                }
-                ProviderState::Configured {
-                    data_sources: _,
-                    resources,
-                } => resources.get(type_name).unwrap().clone(),
-            }
+        provider_meta: &Option<tfplugin6::DynamicValue>,
+    ) -> (
+        Option<tfplugin6::DynamicValue>,
+        Vec<tfplugin6::AttributePath>,
+        Vec<tfplugin6::Diagnostic>,
+    ) {
+        let rs = match self.get_resource(type_name).await {
+            Ok(rs) => rs,
+            Err(errs) => return (None, vec![], errs.into_tfplugin_diags()),
         };
-        let typ = rs.schema.typ();
-        let _prior_state = tf_try!(parse_dynamic_value(prior_state, &typ));
-        let proposed_new_state = tf_try!(parse_dynamic_value(proposed_new_state, &typ));
-        let _config = tf_try!(parse_dynamic_value(config, &typ));
-
-        // TODO: i cannot even start on how bad this is...
-        enum AttrSchema<'a> {
-            Nested(&'a HashMap<String, Attribute>),
-            Computed,
-            End,
-        }
-        fn transform(value: Value, schema: AttrSchema) -> Value {
-            match (value, schema) {
-                (Value::Null, AttrSchema::Computed) => Value::Unknown,
-                (Value::Known(ValueKind::Object(attrs)), AttrSchema::Nested(schema)) => {
-                    Value::Known(ValueKind::Object(
-                        attrs
-                            .into_iter()
-                            .map(|(name, attr)| {
-                                let schema = schema.get(&name).unwrap();
-                                let schema = if schema.mode().computed() {
-                                    AttrSchema::Computed
-                                } else {
-                                    AttrSchema::End
-                                };
-                                (name, transform(attr, schema))
-                            })
-                            .collect(),
-                    ))
-                }
-                (value, _) => value,
+        let mut diags = Diagnostics::default();
+        let plan = self
+            .plan_resource(
+                &rs,
+                prior_state,
+                proposed_new_state,
+                config,
+                provider_meta,
+                &mut diags,
+            )
+            .await;
+
+        match plan {
+            Ok(ResourcePlan {
+                planned_state,
+                requires_replace,
+            }) => (
+                planned_state.into_tfplugin(&rs.schema.typ(), &mut diags),
+                requires_replace
+                    .into_iter()
+                    .map(AttrPath::into_tfplugin)
+                    .collect(),
+                diags.into_tfplugin_diags(),
+            ),
+            Err(errs) => {
+                diags.extend(errs);
+                (None, vec![], diags.into_tfplugin_diags())
             }
         }
-
-        let planned_state = transform(
-            proposed_new_state,
-            AttrSchema::Nested(&rs.schema.attributes),
-        );
-
-        (planned_state.into_tfplugin(), TF_OK)
     }
 
     pub(super) async fn do_apply_resource_change(
@@ -303,26 +364,14 @@ impl<P: Provider> ProviderHandler<P> {
         prior_state: &Option<tfplugin6::DynamicValue>,
         planned_state: &Option<tfplugin6::DynamicValue>,
         config: &Option<tfplugin6::DynamicValue>,
+        provider_meta: &Option<tfplugin6::DynamicValue>,
     ) -> (Option<tfplugin6::DynamicValue>, Vec<tfplugin6::Diagnostic>) {
-        let rs: StoredResource = {
-            let state = self.state.lock().await;
-            match &*state {
-                ProviderState::Setup { .. } => {
-                    unreachable!("must be set up before calling data sources")
-                }
-                ProviderState::Failed { diags } => {
-                    return (None, diags.clone().into_tfplugin_diags())
-                }
-                ProviderState::Configured {
-                    data_sources: _,
-                    resources,
-                } => resources.get(type_name).unwrap().clone(),
-            }
-        };
+        let rs = tf_try!(self.get_resource(type_name).await);
         let typ = rs.schema.typ();
         let prior_state = tf_try!(parse_dynamic_value(prior_state, &typ));
         let planned_state = tf_try!(parse_dynamic_value(planned_state, &typ));
         let config = tf_try!(parse_dynamic_value(config, &typ));
+        let provider_meta = tf_try!(self.parse_provider_meta(provider_meta));
 
         debug!(
             ?prior_state,
@@ -331,22 +380,284 @@ impl<P: Provider> ProviderHandler<P> {
             "Applying resource change"
         );
 
+        let mut diags = Diagnostics::default();
         let new_state = if prior_state.is_null() {
             debug!("Change is create");
-            tf_try!(rs.rs.create(config, planned_state).await)
+            tf_try!(
+                catch_unwind(
+                    rs.rs
+                        .create(config, planned_state, provider_meta, &mut diags)
+                )
+                .await,
+                diags
+            )
         } else if planned_state.is_null() {
             debug!("Change is delete");
-            tf_try!(rs.rs.delete(config).await);
+            tf_try!(
+                catch_unwind(rs.rs.delete(prior_state, provider_meta, &mut diags)).await,
+                diags
+            );
             Value::Null
         } else {
             debug!("Change is udpate");
-            tf_try!(rs.rs.update(config, planned_state, prior_state).await)
+            tf_try!(
+                catch_unwind(rs.rs.update(
+                    config,
+                    planned_state,
+                    prior_state,
+                    provider_meta,
+                    &mut diags
+                ))
+                .await,
+                diags
+            )
         };
 
         info!(?new_state, "Hello world");
 
-        (new_state.into_tfplugin(), TF_OK)
+        (
+            new_state.into_tfplugin(&typ, &mut diags),
+            diags.into_tfplugin_diags(),
+        )
     }
+
+    pub(super) async fn do_import_resource_state(
+        &self,
+        type_name: &str,
+        id: String,
+    ) -> (
+        Vec<tfplugin6::import_resource_state::ImportedResource>,
+        Vec<tfplugin6::Diagnostic>,
+    ) {
+        let rs = match self.get_resource(type_name).await {
+            Ok(rs) => rs,
+            Err(errs) => return (vec![], errs.into_tfplugin_diags()),
+        };
+
+        debug!(?id, "Importing resource");
+
+        let mut diags = Diagnostics::default();
+        let state = match catch_unwind(rs.rs.import(id, &mut diags)).await {
+            Ok(state) => state,
+            Err(errs) => {
+                diags.extend(errs);
+                return (vec![], diags.into_tfplugin_diags());
+            }
+        };
+
+        let state = state.into_tfplugin(&rs.schema.typ(), &mut diags);
+        if diags.has_errors() {
+            return (vec![], diags.into_tfplugin_diags());
+        }
+
+        let imported = tfplugin6::import_resource_state::ImportedResource {
+            type_name: type_name.to_owned(),
+            state,
+            private: vec![],
+        };
+
+        (vec![imported], diags.into_tfplugin_diags())
+    }
+
+    pub(super) async fn do_upgrade_resource_state(
+        &self,
+        type_name: &str,
+        version: i64,
+        raw_state: &Option<tfplugin6::RawState>,
+    ) -> (Option<tfplugin6::DynamicValue>, Vec<tfplugin6::Diagnostic>) {
+        // This may be called before the provider is configured, in which case the static state upgraders
+        // are run instead of `Resource::upgrade_state`.
+        let (schema, _, state_upgraders, rs) = tf_try!(self.get_resource_schema(type_name).await);
+
+        let Some(raw_state) = raw_state else {
+            return (None, TF_OK);
+        };
+
+        if version > schema.version {
+            return (
+                None,
+                Diagnostics::from(Diagnostic::error_string(format!(
+                    "The state was stored with schema version {version}, which is newer than the current schema version {}. \
+                     Was it created by a newer version of the provider?",
+                    schema.version
+                )))
+                .into_tfplugin_diags(),
+            );
+        }
+
+        let mut diags = Diagnostics::default();
+        if version == schema.version && !raw_state.json.is_empty() {
+            // Decoding the JSON directly keeps numbers that don't fit into an `f64`.
+            let state = tf_try!(Value::json_unpack(&raw_state.json, &schema.typ()));
+            return (
+                state.into_tfplugin(&schema.typ(), &mut diags),
+                diags.into_tfplugin_diags(),
+            );
+        }
+
+        let raw_state = if !raw_state.json.is_empty() {
+            RawState::Json(tf_try!(
+                serde_json::from_slice(&raw_state.json).map_err(Diagnostics::from)
+            ))
+        } else {
+            RawState::Flatmap(raw_state.flatmap.clone())
+        };
+
+        let upgraded_state = if version == schema.version {
+            tf_try!(upgrade_state_chain(&schema, &[], version, raw_state))
+        } else if let Some(rs) = rs {
+            debug!(?version, current_version = ?schema.version, "Upgrading resource state");
+            tf_try!(
+                catch_unwind(rs.rs.upgrade_state(version, raw_state, &mut diags)).await,
+                diags
+            )
+        } else {
+            debug!(?version, current_version = ?schema.version, "Upgrading resource state before configuration");
+            tf_try!(catch_unwind_sync(|| upgrade_state_chain(
+                &schema,
+                &state_upgraders,
+                version,
+                raw_state
+            )))
+        };
+
+        (
+            upgraded_state.into_tfplugin(&schema.typ(), &mut diags),
+            diags.into_tfplugin_diags(),
+        )
+    }
+
+    async fn plan_resource(
+        &self,
+        rs: &StoredResource,
+        prior_state: &Option<tfplugin6::DynamicValue>,
+        proposed_new_state: &Option<tfplugin6::DynamicValue>,
+        config: &Option<tfplugin6::DynamicValue>,
+        provider_meta: &Option<tfplugin6::DynamicValue>,
+        diags: &mut Diagnostics,
+    ) -> DResult<ResourcePlan> {
+        let typ = rs.schema.typ();
+        let prior_state = parse_dynamic_value(prior_state, &typ)?;
+        let proposed_new_state = parse_dynamic_value(proposed_new_state, &typ)?;
+        let config = parse_dynamic_value(config, &typ)?;
+        let provider_meta = self.parse_provider_meta(provider_meta)?;
+
+        // Plan modifiers and dynamic defaults are user code.
+        let (planned_state, requires_replace) =
+            catch_unwind_sync(|| rs.schema.plan(&config, &prior_state, proposed_new_state))?;
+        let plan = ResourcePlan {
+            planned_state,
+            requires_replace,
+        };
+
+        debug!(?plan.planned_state, "Modifying plan");
+        catch_unwind(
+            rs.rs
+                .modify_plan(config, prior_state, plan, provider_meta, diags),
+        )
+        .await
+    }
+
+    /// Looks up a resource, which is only possible after the provider has been configured.
+    async fn get_resource(&self, type_name: &str) -> DResult<StoredResource> {
+        let state = self.state.lock().await;
+        match &*state {
+            ProviderState::Setup { .. } => Err(not_configured("resource", type_name)),
+            ProviderState::Failed { diags } => Err(diags.clone()),
+            ProviderState::Configured { resources, .. } => resources
+                .get(type_name)
+                .cloned()
+                .ok_or_else(|| unknown_type("resource", type_name)),
+        }
+    }
+
+    /// Looks up a data source, which is only possible after the provider has been configured.
+    async fn get_data_source(&self, type_name: &str) -> DResult<StoredDataSource> {
+        let state = self.state.lock().await;
+        match &*state {
+            ProviderState::Setup { .. } => Err(not_configured("data source", type_name)),
+            ProviderState::Failed { diags } => Err(diags.clone()),
+            ProviderState::Configured { data_sources, .. } => data_sources
+                .get(type_name)
+                .cloned()
+                .ok_or_else(|| unknown_type("data source", type_name)),
+        }
+    }
+
+    /// Looks up the schema, config validator and state upgraders of a resource, which are also available
+    /// before the provider has been configured. The resource itself is only returned if it has been.
+    async fn get_resource_schema(
+        &self,
+        type_name: &str,
+    ) -> DResult<(
+        Schema,
+        ValidateConfigFn,
+        Vec<StateUpgrader>,
+        Option<StoredResource>,
+    )> {
+        let state = self.state.lock().await;
+        match &*state {
+            ProviderState::Setup { mk_rs, .. } => mk_rs
+                .get(type_name)
+                .map(|rs| {
+                    let upgraders = rs.state_upgraders.clone();
+                    (rs.schema.clone(), rs.validate_config, upgraders, None)
+                })
+                .ok_or_else(|| unknown_type("resource", type_name)),
+            ProviderState::Failed { diags } => Err(diags.clone()),
+            // The configured resource upgrades state itself.
+            ProviderState::Configured { resources, .. } => resources
+                .get(type_name)
+                .map(|rs| {
+                    let rs = rs.clone();
+                    (rs.schema.clone(), rs.validate_config, Vec::new(), Some(rs))
+                })
+                .ok_or_else(|| unknown_type("resource", type_name)),
+        }
+    }
+
+    /// Looks up the schema and config validator of a data source, which are also available
+    /// before the provider has been configured.
+    async fn get_data_source_schema(&self, type_name: &str) -> DResult<(Schema, ValidateConfigFn)> {
+        let state = self.state.lock().await;
+        match &*state {
+            ProviderState::Setup { mk_ds, .. } => mk_ds
+                .get(type_name)
+                .map(|ds| (ds.schema.clone(), ds.validate_config))
+                .ok_or_else(|| unknown_type("data source", type_name)),
+            ProviderState::Failed { diags } => Err(diags.clone()),
+            ProviderState::Configured { data_sources, .. } => data_sources
+                .get(type_name)
+                .map(|ds| (ds.schema.clone(), ds.validate_config))
+                .ok_or_else(|| unknown_type("data source", type_name)),
+        }
+    }
+
+    fn parse_provider_meta(
+        &self,
+        provider_meta: &Option<tfplugin6::DynamicValue>,
+    ) -> DResult<Value> {
+        parse_dynamic_value(provider_meta, &self.provider_meta_schema.typ())
+    }
+}
+
+fn schemas<'a>(
+    schemas: impl Iterator<Item = (&'a String, &'a Schema)>,
+) -> HashMap<String, tfplugin6::Schema> {
+    schemas
+        .map(|(name, schema)| (name.clone(), schema.clone().into_tfplugin()))
+        .collect()
+}
+
+fn not_configured(kind: &str, type_name: &str) -> Diagnostics {
+    Diagnostic::error_string(format!(
+        "Cannot use {kind} {type_name} before the provider has been configured"
+    ))
+    .into()
+}
+
+fn unknown_type(kind: &str, type_name: &str) -> Diagnostics {
+    Diagnostic::error_string(format!("Unknown {kind} type {type_name}")).into()
 }
 
 macro_rules! tf_try {
@@ -358,12 +669,28 @@ macro_rules! tf_try {
             }
         }
     };
+    // Also reports the warnings that were collected in `$diags` so far.
+    ($e:expr, $diags:ident) => {
+        match $e {
+            Ok(value) => value,
+            Err(errs) => {
+                $diags.extend(errs);
+                return (None, $diags.into_tfplugin_diags());
+            }
+        }
+    };
 }
 use tf_try;
 
+/// Decodes whichever encoding Terraform used, preferring msgpack like terraform-plugin-go.
 fn parse_dynamic_value(value: &Option<tfplugin6::DynamicValue>, typ: &Type) -> DResult<Value> {
     match value {
         None => Ok(Value::Null),
-        Some(v) => Value::msg_unpack(&v.msgpack, typ),
+        Some(v) if !v.msgpack.is_empty() => Value::msg_unpack(&v.msgpack, typ),
+        Some(v) if !v.json.is_empty() => Value::json_unpack(&v.json, typ),
+        Some(_) => Err(Diagnostic::error_string(
+            "dynamic value has neither msgpack nor JSON data set",
+        )
+        .into()),
     }
 }
@@ -1,3 +1,4 @@
+mod catch_unwind;
 mod cert;
 mod convert;
 mod grpc;
@@ -24,6 +25,8 @@ use self::handler::ProviderHandler;
 
 #[derive(Debug)]
 struct Schemas {
+    provider: tfplugin6::Schema,
+    provider_meta: tfplugin6::Schema,
     resources: HashMap<String, tfplugin6::Schema>,
     data_sources: HashMap<String, tfplugin6::Schema>,
     diagnostics: Vec<tfplugin6::Diagnostic>,
@@ -0,0 +1,204 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::{DResult, Diagnostic, Type, Value};
+
+/// The elements of a set value.
+///
+/// Sets are unordered and contain every element only once: two sets are equal if they have the same elements
+/// in any order, and duplicates are removed when a set is constructed.
+/// Elements that are not fully known are never duplicates, as they may turn out to be different values.
+#[derive(Debug, Clone, Default)]
+pub struct ValueSet(pub(crate) Vec<Value>);
+
+impl ValueSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an element to the set. Returns whether the element was added, which is not the case if the set
+    /// already contained it.
+    pub fn insert(&mut self, value: Value) -> bool {
+        if self.contains_duplicate(&value) {
+            return false;
+        }
+        self.0.push(value);
+        true
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        self.0
+    }
+
+    /// Creates a set from elements of type `typ` received from Terraform, which must not contain duplicates.
+    pub(crate) fn from_unique(elems: Vec<Value>, typ: &Type) -> DResult<Self> {
+        let mut set = Self(Vec::with_capacity(elems.len()));
+        for elem in elems {
+            if set.contains_duplicate(&elem) {
+                let value = elem
+                    .json_pack(typ)
+                    .map(|json| String::from_utf8_lossy(&json).into_owned())
+                    .unwrap_or_else(|_| format!("{elem:?}"));
+                return Err(Diagnostic::error(
+                    "Duplicate Set Element",
+                    format!("This attribute contains duplicate values of: {value}"),
+                )
+                .into());
+            }
+            set.0.push(elem);
+        }
+        Ok(set)
+    }
+
+    fn contains_duplicate(&self, value: &Value) -> bool {
+        value.is_fully_known() && self.0.contains(value)
+    }
+}
+
+impl Deref for ValueSet {
+    type Target = [Value];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PartialEq for ValueSet {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        // Elements that are not fully known may occur more than once, so each element needs its own match.
+        let mut matched = vec![false; other.len()];
+        self.iter().all(|elem| {
+            let found = other
+                .iter()
+                .enumerate()
+                .find(|(i, other)| !matched[*i] && elem == *other);
+            match found {
+                Some((i, _)) => {
+                    matched[i] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+impl Eq for ValueSet {}
+
+/// The hash doesn't depend on the order of the elements, like equality.
+impl Hash for ValueSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut hashes = self
+            .iter()
+            .map(|elem| {
+                let mut hasher = DefaultHasher::new();
+                elem.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+        hashes.sort_unstable();
+        hashes.hash(state);
+    }
+}
+
+impl FromIterator<Value> for ValueSet {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Value> for ValueSet {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl From<Vec<Value>> for ValueSet {
+    fn from(elems: Vec<Value>) -> Self {
+        elems.into_iter().collect()
+    }
+}
+
+impl IntoIterator for ValueSet {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ValueSet {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use crate::{Type, Value, ValueKind};
+
+    use super::ValueSet;
+
+    fn string(s: &str) -> Value {
+        Value::Known(ValueKind::String(s.to_owned()))
+    }
+
+    fn hash(set: &ValueSet) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        set.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn order_and_duplicates() {
+        let a = ValueSet::from(vec![string("a"), string("b"), string("a")]);
+        let b = ValueSet::from(vec![string("b"), string("a")]);
+        assert_eq!(a.len(), 2);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(a, ValueSet::from(vec![string("a")]));
+
+        let nested = |sets: [&ValueSet; 2]| {
+            ValueSet::from_iter(sets.map(|set| Value::Known(ValueKind::Set(set.clone()))))
+        };
+        assert_eq!(nested([&a, &b]).len(), 1);
+
+        let unknowns = ValueSet::from(vec![Value::unknown(), Value::unknown(), string("a")]);
+        assert_eq!(unknowns.len(), 3);
+        assert_ne!(
+            unknowns,
+            ValueSet::from(vec![Value::unknown(), string("a")])
+        );
+    }
+
+    #[test]
+    fn duplicate_error() {
+        assert!(ValueSet::from_unique(vec![string("a"), string("b")], &Type::String).is_ok());
+        let err = ValueSet::from_unique(vec![string("a"), string("a")], &Type::String).unwrap_err();
+        let diag = err.iter().next().unwrap();
+        assert_eq!(diag.msg, "Duplicate Set Element");
+        assert_eq!(
+            diag.detail,
+            r#"This attribute contains duplicate values of: "a""#
+        );
+    }
+}
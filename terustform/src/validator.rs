@@ -0,0 +1,590 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+};
+
+use crate::{
+    AttrPath, Attribute, Block, DResult, Diagnostic, Diagnostics, Number, Schema, Value, ValueKind,
+    ValueSet,
+};
+
+type ValidateFn = dyn Fn(&ValueKind, &AttrPath) -> DResult<()> + Send + Sync;
+type ValidateConfigFn = dyn Fn(&Value) -> DResult<()> + Send + Sync;
+
+/// A check for the value of an attribute, run when Terraform validates the configuration.
+///
+/// Validators only see known, non-null values.
+/// Unknown values are skipped, as they can only be validated once they are known during apply.
+#[derive(Clone)]
+pub struct Validator {
+    description: String,
+    validate: Arc<ValidateFn>,
+}
+
+impl Validator {
+    /// Creates a custom validator. The `description` should describe what the validator checks,
+    /// like "value must be a valid email address".
+    pub fn custom(
+        description: impl Into<String>,
+        validate: impl Fn(&ValueKind, &AttrPath) -> DResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            validate: Arc::new(validate),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn validate(&self, value: &ValueKind, path: &AttrPath) -> DResult<()> {
+        (self.validate)(value, path)
+    }
+
+    /// The length of the string in characters must be in `range`.
+    pub fn string_length(range: impl RangeBounds<usize> + Send + Sync + 'static) -> Self {
+        let description = format!("string length must be {}", describe_range(&range));
+        Self::custom(description.clone(), move |value, path| {
+            let s = expect_string(value, path)?;
+            let len = s.chars().count();
+            if !range.contains(&len) {
+                return Err(error(path, format!("{description}, got {len}")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The string must match `regex`. `message` describes the expected format for the user.
+    pub fn regex(regex: regex::Regex, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::custom(message.clone(), move |value, path| {
+            let s = expect_string(value, path)?;
+            if !regex.is_match(s) {
+                return Err(error(path, format!("{message}, got '{s}'")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The string must be one of `values`.
+    pub fn one_of(values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let values = values.into_iter().map(Into::into).collect::<Vec<String>>();
+        let description = format!(
+            "value must be one of: {}",
+            values
+                .iter()
+                .map(|v| format!("'{v}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Self::custom(description.clone(), move |value, path| {
+            let s = expect_string(value, path)?;
+            if !values.iter().any(|v| v == s) {
+                return Err(error(path, format!("{description}, got '{s}'")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The string must be a UUID in its hyphenated form, like `f245514b-f99c-4c09-ab53-eabd944af6d2`.
+    pub fn uuid() -> Self {
+        let description = "value must be a UUID";
+        Self::custom(description, move |value, path| {
+            let s = expect_string(value, path)?;
+            let is_uuid = s.len() == 36
+                && s.char_indices().all(|(i, c)| match i {
+                    8 | 13 | 18 | 23 => c == '-',
+                    _ => c.is_ascii_hexdigit(),
+                });
+            if !is_uuid {
+                return Err(error(path, format!("{description}, got '{s}'")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The number must be an integer in `range`.
+    pub fn int_range(range: impl RangeBounds<i64> + Send + Sync + 'static) -> Self {
+        let description = format!("value must be an integer {}", describe_range(&range));
+        Self::custom(description.clone(), move |value, path| {
+            let n = expect_number(value, path)?;
+            if !n.as_i64().is_some_and(|int| range.contains(&int)) {
+                return Err(error(path, format!("{description}, got {n}")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The number must be in `range`.
+    pub fn float_range(range: impl RangeBounds<f64> + Send + Sync + 'static) -> Self {
+        let description = format!("value must be {}", describe_range(&range));
+        Self::custom(description.clone(), move |value, path| {
+            let n = expect_number(value, path)?;
+            if !range.contains(&n.as_f64()) {
+                return Err(error(path, format!("{description}, got {n}")));
+            }
+            Ok(())
+        })
+    }
+
+    /// The number of elements of the list, set or map must be in `range`.
+    pub fn size(range: impl RangeBounds<usize> + Send + Sync + 'static) -> Self {
+        let description = format!("number of elements must be {}", describe_range(&range));
+        Self::custom(description.clone(), move |value, path| {
+            let len = match value {
+                ValueKind::List(elems) | ValueKind::Set(ValueSet(elems)) => elems.len(),
+                ValueKind::Map(elems) => elems.len(),
+                _ => return Err(type_error(path, "list, set or map", value)),
+            };
+            if !range.contains(&len) {
+                return Err(error(path, format!("{description}, got {len}")));
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs `validators` on every known element of a list, set or map.
+    /// Set elements cannot be addressed in the protocol, so their errors point at the set.
+    pub(crate) fn elements(validators: Vec<Validator>) -> Self {
+        let description = format!(
+            "every element: {}",
+            validators
+                .iter()
+                .map(Validator::description)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Self::custom(description, move |value, path| {
+            let elems: Vec<(&Value, AttrPath)> = match value {
+                ValueKind::List(elems) => elems
+                    .iter()
+                    .enumerate()
+                    .map(|(i, elem)| (elem, path.append_element_key_int(i as i64)))
+                    .collect(),
+                ValueKind::Set(elems) => elems.iter().map(|elem| (elem, path.clone())).collect(),
+                ValueKind::Map(elems) => elems
+                    .iter()
+                    .map(|(key, elem)| (elem, path.append_element_key_string(key.clone())))
+                    .collect(),
+                _ => return Err(type_error(path, "list, set or map", value)),
+            };
+            let mut diags = Diagnostics::default();
+            for (elem, path) in elems {
+                let Value::Known(elem) = elem else {
+                    continue;
+                };
+                for validator in &validators {
+                    if let Err(errs) = validator.validate(elem, &path) {
+                        diags.extend(errs);
+                    }
+                }
+            }
+            if diags.has_errors() {
+                return Err(diags);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// A check for a whole configuration, used for constraints between multiple attributes
+/// like "exactly one of `source_url` and `source_file` must be set".
+///
+/// Unlike [`Validator`]s, config validators can see unknown values, which they should skip.
+/// The built-in validators do nothing if any of the involved attributes is unknown.
+#[derive(Clone)]
+pub struct ConfigValidator {
+    description: String,
+    validate: Arc<ValidateConfigFn>,
+}
+
+impl ConfigValidator {
+    /// Creates a custom config validator. The `description` should describe what the validator checks.
+    pub fn custom(
+        description: impl Into<String>,
+        validate: impl Fn(&Value) -> DResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            validate: Arc::new(validate),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn validate(&self, config: &Value) -> DResult<()> {
+        (self.validate)(config)
+    }
+
+    /// At most one of the attributes at `paths` may be set.
+    pub fn conflicts_with(paths: impl IntoIterator<Item = AttrPath>) -> Self {
+        let paths = paths.into_iter().collect::<Vec<_>>();
+        let description = format!("at most one of {} may be set", describe_paths(&paths));
+        Self::custom(description, move |config| {
+            let Some(set) = set_paths(config, &paths) else {
+                return Ok(());
+            };
+            if set.len() > 1 {
+                return Err(conflicting_errors(&set));
+            }
+            Ok(())
+        })
+    }
+
+    /// Exactly one of the attributes at `paths` must be set.
+    pub fn exactly_one_of(paths: impl IntoIterator<Item = AttrPath>) -> Self {
+        let paths = paths.into_iter().collect::<Vec<_>>();
+        let description = format!("exactly one of {} must be set", describe_paths(&paths));
+        Self::custom(description.clone(), move |config| {
+            let Some(set) = set_paths(config, &paths) else {
+                return Ok(());
+            };
+            match set.len() {
+                0 => Err(Diagnostic::error_string(format!(
+                    "Invalid attribute combination: {description}"
+                ))
+                .into()),
+                1 => Ok(()),
+                _ => Err(conflicting_errors(&set)),
+            }
+        })
+    }
+
+    /// At least one of the attributes at `paths` must be set.
+    pub fn at_least_one_of(paths: impl IntoIterator<Item = AttrPath>) -> Self {
+        let paths = paths.into_iter().collect::<Vec<_>>();
+        let description = format!("at least one of {} must be set", describe_paths(&paths));
+        Self::custom(description.clone(), move |config| {
+            let Some(set) = set_paths(config, &paths) else {
+                return Ok(());
+            };
+            if set.is_empty() {
+                return Err(Diagnostic::error_string(format!(
+                    "Invalid attribute combination: {description}"
+                ))
+                .into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Either all or none of the attributes at `paths` must be set.
+    pub fn required_together(paths: impl IntoIterator<Item = AttrPath>) -> Self {
+        let paths = paths.into_iter().collect::<Vec<_>>();
+        let description = format!("{} must be set together", describe_paths(&paths));
+        Self::custom(description.clone(), move |config| {
+            let Some(set) = set_paths(config, &paths) else {
+                return Ok(());
+            };
+            if set.is_empty() || set.len() == paths.len() {
+                return Ok(());
+            }
+            let mut diags = Diagnostics::default();
+            for path in paths.iter().filter(|path| !set.contains(path)) {
+                diags.push(
+                    Diagnostic::error_string(format!(
+                        "Invalid attribute combination: {description}, but '{path}' is missing"
+                    ))
+                    .with_path(path.clone()),
+                );
+            }
+            Err(diags)
+        })
+    }
+
+    /// If the attribute at `path` is set, the attributes at `required` must be set too.
+    pub fn required_with(path: AttrPath, required: impl IntoIterator<Item = AttrPath>) -> Self {
+        let required = required.into_iter().collect::<Vec<_>>();
+        let description = format!(
+            "{} must be set when '{path}' is set",
+            describe_paths(&required)
+        );
+        Self::custom(description.clone(), move |config| {
+            let Some(set) = set_paths(config, std::slice::from_ref(&path)) else {
+                return Ok(());
+            };
+            if set.is_empty() {
+                return Ok(());
+            }
+            let Some(required_set) = set_paths(config, &required) else {
+                return Ok(());
+            };
+            let mut diags = Diagnostics::default();
+            for missing in required.iter().filter(|r| !required_set.contains(r)) {
+                diags.push(
+                    Diagnostic::error_string(format!(
+                        "Invalid attribute combination: '{missing}' must be set when '{path}' is set"
+                    ))
+                    .with_path(path.clone()),
+                );
+            }
+            if diags.has_errors() {
+                return Err(diags);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Returns the paths of `paths` that are set (not null) in `config`,
+/// or `None` if any of them is unknown.
+fn set_paths<'a>(config: &Value, paths: &'a [AttrPath]) -> Option<Vec<&'a AttrPath>> {
+    let mut set = Vec::new();
+    for path in paths {
+        match config.get_path(path) {
+            Some(Value::Unknown(_)) => return None,
+            Some(Value::Known(_)) => set.push(path),
+            Some(Value::Null) | None => {}
+        }
+    }
+    Some(set)
+}
+
+fn conflicting_errors(set: &[&AttrPath]) -> Diagnostics {
+    let mut diags = Diagnostics::default();
+    for path in set {
+        let others = set
+            .iter()
+            .filter(|other| *other != path)
+            .map(|other| format!("'{other}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        diags.push(
+            Diagnostic::error_string(format!(
+                "Invalid attribute combination: '{path}' cannot be set together with {others}"
+            ))
+            .with_path((*path).clone()),
+        );
+    }
+    diags
+}
+
+fn describe_paths(paths: &[AttrPath]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("'{path}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Schema {
+    /// Runs the validators of all attributes on the known parts of `value`
+    /// and the config validators of the schema on the whole `value`.
+    pub(crate) fn validate(&self, value: &Value) -> Diagnostics {
+        let mut diags = Diagnostics::default();
+        if let Value::Known(ValueKind::Object(values)) = value {
+            validate_object(
+                values,
+                &self.attributes,
+                &self.blocks,
+                &AttrPath::root(),
+                &mut diags,
+            );
+        }
+        for validator in &self.validators {
+            if let Err(errs) = validator.validate(value) {
+                diags.extend(errs);
+            }
+        }
+        diags
+    }
+}
+
+fn validate_object(
+    values: &BTreeMap<String, Value>,
+    attrs: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+    path: &AttrPath,
+    diags: &mut Diagnostics,
+) {
+    for (name, value) in values {
+        let Value::Known(value) = value else {
+            continue;
+        };
+        let path = path.append_attribute_name(name.clone());
+
+        if let Some(attr) = attrs.get(name) {
+            for validator in attr.validators() {
+                if let Err(errs) = validator.validate(value, &path) {
+                    diags.extend(errs);
+                }
+            }
+            if let Some((_, attrs)) = attr.nested() {
+                validate_nested_objects(value, attrs, &HashMap::new(), &path, diags);
+            }
+        } else if let Some(block) = blocks.get(name) {
+            validate_nested_objects(value, &block.attributes, &block.blocks, &path, diags);
+        }
+    }
+}
+
+/// Validates the objects of a nested attribute or block, which are either a single object
+/// or the elements of a collection.
+fn validate_nested_objects(
+    value: &ValueKind,
+    attrs: &HashMap<String, Attribute>,
+    blocks: &HashMap<String, Block>,
+    path: &AttrPath,
+    diags: &mut Diagnostics,
+) {
+    let mut validate_elem = |elem: &Value, path: &AttrPath| {
+        if let Value::Known(ValueKind::Object(values)) = elem {
+            validate_object(values, attrs, blocks, path, diags);
+        }
+    };
+
+    match value {
+        ValueKind::Object(values) => validate_object(values, attrs, blocks, path, diags),
+        ValueKind::List(elems) => {
+            for (i, elem) in elems.iter().enumerate() {
+                validate_elem(elem, &path.append_element_key_int(i as i64));
+            }
+        }
+        // Set elements cannot be addressed in the protocol, so we point at the set.
+        ValueKind::Set(elems) => {
+            for elem in elems {
+                validate_elem(elem, path);
+            }
+        }
+        ValueKind::Map(elems) => {
+            for (key, elem) in elems {
+                validate_elem(elem, &path.append_element_key_string(key.clone()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn error(path: &AttrPath, msg: String) -> Diagnostics {
+    Diagnostic::error_string(format!("Invalid attribute value: {msg}"))
+        .with_path(path.clone())
+        .into()
+}
+
+fn type_error(path: &AttrPath, expected: &str, value: &ValueKind) -> Diagnostics {
+    Diagnostic::error_string(format!(
+        "Validator expected {expected}, found {} value, this is a bug in the provider",
+        value.diagnostic_type_str()
+    ))
+    .with_path(path.clone())
+    .into()
+}
+
+fn expect_string<'a>(value: &'a ValueKind, path: &AttrPath) -> DResult<&'a str> {
+    match value {
+        ValueKind::String(s) => Ok(s),
+        _ => Err(type_error(path, "string", value)),
+    }
+}
+
+fn expect_number<'a>(value: &'a ValueKind, path: &AttrPath) -> DResult<&'a Number> {
+    match value {
+        ValueKind::Number(n) => Ok(n),
+        _ => Err(type_error(path, "number", value)),
+    }
+}
+
+fn describe_range<T: Display>(range: &impl RangeBounds<T>) -> String {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Unbounded, Bound::Unbounded) => "anything".to_owned(),
+        (Bound::Included(start), Bound::Unbounded) => format!("at least {start}"),
+        (Bound::Excluded(start), Bound::Unbounded) => format!("greater than {start}"),
+        (Bound::Unbounded, Bound::Included(end)) => format!("at most {end}"),
+        (Bound::Unbounded, Bound::Excluded(end)) => format!("less than {end}"),
+        (start, end) => {
+            let start = match start {
+                Bound::Included(start) => format!("{start} (inclusive)"),
+                Bound::Excluded(start) => format!("{start} (exclusive)"),
+                Bound::Unbounded => unreachable!(),
+            };
+            let end = match end {
+                Bound::Included(end) => format!("{end} (inclusive)"),
+                Bound::Excluded(end) => format!("{end} (exclusive)"),
+                Bound::Unbounded => unreachable!(),
+            };
+            format!("between {start} and {end}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use crate::{AttrPath, Value, ValueKind};
+
+    use super::{ConfigValidator, Validator};
+
+    #[test]
+    fn string_validators() {
+        let path = AttrPath::attr("id");
+        let string = |s: &str| ValueKind::String(s.to_owned());
+
+        let uuid = Validator::uuid();
+        assert!(uuid
+            .validate(&string("f245514b-f99c-4c09-ab53-eabd944af6d2"), &path)
+            .is_ok());
+        assert!(uuid
+            .validate(&string("f245514b_f99c-4c09-ab53-eabd944af6d2"), &path)
+            .is_err());
+        assert!(uuid.validate(&string("meow"), &path).is_err());
+
+        let length = Validator::string_length(1..=3);
+        assert!(length.validate(&string("mew"), &path).is_ok());
+        assert!(length.validate(&string(""), &path).is_err());
+        assert!(length.validate(&string("meow"), &path).is_err());
+        assert_eq!(
+            length.description(),
+            "string length must be between 1 (inclusive) and 3 (inclusive)"
+        );
+
+        let one_of = Validator::one_of(["tcp", "udp"]);
+        assert!(one_of.validate(&string("udp"), &path).is_ok());
+        assert!(one_of.validate(&string("icmp"), &path).is_err());
+        assert!(one_of.validate(&ValueKind::Bool(true), &path).is_err());
+    }
+
+    #[test]
+    fn number_validators() {
+        let path = AttrPath::attr("port");
+
+        let port = Validator::int_range(1..=65535);
+        assert!(port.validate(&ValueKind::Number(443.into()), &path).is_ok());
+        assert!(port.validate(&ValueKind::Number(0.into()), &path).is_err());
+        assert!(port
+            .validate(&ValueKind::Number(1.5.into()), &path)
+            .is_err());
+    }
+
+    #[test]
+    fn config_validators() {
+        let config = |url: Value, file: Value| {
+            Value::Known(ValueKind::Object(BTreeMap::from([
+                ("url".to_owned(), url),
+                ("file".to_owned(), file),
+            ])))
+        };
+        let set = || Value::Known(ValueKind::Bool(true));
+        let paths = || [AttrPath::attr("url"), AttrPath::attr("file")];
+
+        let exactly_one = ConfigValidator::exactly_one_of(paths());
+        assert!(exactly_one.validate(&config(set(), Value::Null)).is_ok());
+        assert!(exactly_one
+            .validate(&config(Value::Null, Value::Null))
+            .is_err());
+        assert!(exactly_one.validate(&config(set(), set())).is_err());
+        assert!(exactly_one
+            .validate(&config(Value::unknown(), set()))
+            .is_ok());
+
+        let together = ConfigValidator::required_together(paths());
+        assert!(together.validate(&config(Value::Null, Value::Null)).is_ok());
+        let errs = together.validate(&config(set(), Value::Null)).unwrap_err();
+        assert_eq!(errs.diags.len(), 1);
+    }
+}
@@ -6,9 +6,13 @@ use std::{
     io::{self, Read},
 };
 
-use crate::{AttrPath, DResult, Diagnostic};
+use serde_json::value::RawValue;
 
-#[derive(Debug)]
+use crate::{
+    AttrPath, AttrPathSegment, DResult, Diagnostic, Diagnostics, Number, Refinements, ValueSet,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Bool,
     Number,
@@ -41,6 +45,69 @@ pub enum Type {
 }
 
 impl Type {
+    /// Parses a type from its JSON representation, the inverse of [`Type::to_json`].
+    // tftypes/type_json.go:ParseJSONType
+    pub fn from_json(data: &[u8]) -> DResult<Self> {
+        let json = serde_json::from_slice::<serde_json::Value>(data)?;
+        Self::from_json_inner(&json)
+    }
+
+    pub fn from_json_inner(json: &serde_json::Value) -> DResult<Self> {
+        use serde_json::Value;
+
+        let invalid = || -> DResult<Self> {
+            Err(Diagnostic::error_string(format!("invalid type: {json}")).into())
+        };
+        let elem =
+            |elem: &Value| -> DResult<Box<Type>> { Ok(Box::new(Self::from_json_inner(elem)?)) };
+
+        let typ = match json {
+            Value::String(s) => match s.as_str() {
+                "bool" => Self::Bool,
+                "number" => Self::Number,
+                "string" => Self::String,
+                "dynamic" => Self::Dynamic,
+                _ => return invalid(),
+            },
+            Value::Array(parts) => match parts.as_slice() {
+                [Value::String(tag), inner] if tag == "list" => Self::List { elem: elem(inner)? },
+                [Value::String(tag), inner] if tag == "map" => Self::Map { elem: elem(inner)? },
+                [Value::String(tag), inner] if tag == "set" => Self::Set { elem: elem(inner)? },
+                [Value::String(tag), Value::Array(elems)] if tag == "tuple" => Self::Tuple {
+                    elems: elems
+                        .iter()
+                        .map(Self::from_json_inner)
+                        .collect::<DResult<_>>()?,
+                },
+                [Value::String(tag), Value::Object(attrs), optionals @ ..]
+                    if tag == "object" && optionals.len() <= 1 =>
+                {
+                    let optionals = match optionals {
+                        [Value::Array(optionals)] => optionals
+                            .iter()
+                            .map(|v| v.as_str().map(str::to_owned))
+                            .collect::<Option<Vec<_>>>(),
+                        [] => Some(vec![]),
+                        _ => None,
+                    };
+                    let Some(optionals) = optionals else {
+                        return invalid();
+                    };
+                    Self::Object {
+                        attrs: attrs
+                            .iter()
+                            .map(|(k, v)| Ok((k.clone(), Self::from_json_inner(v)?)))
+                            .collect::<DResult<_>>()?,
+                        optionals,
+                    }
+                }
+                _ => return invalid(),
+            },
+            _ => return invalid(),
+        };
+        Ok(typ)
+    }
+
     // tftypes/type.go
     // https://github.com/hashicorp/terraform-plugin-go/blob/05dc75aefa5b71406022d0ac08eca99f44fbf378/tftypes/type.go#L95
     pub fn to_json(&self) -> String {
@@ -90,16 +157,18 @@ impl Type {
 
 pub type Value = BaseValue<ValueKind>;
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum ValueKind {
     String(String),
-    Number(f64),
+    Number(Number),
     Bool(bool),
     List(Vec<Value>),
-    Set(Vec<Value>),
+    Set(ValueSet),
     Map(BTreeMap<String, Value>),
     Tuple(Vec<Value>),
     Object(BTreeMap<String, Value>),
+    /// A value of a dynamic attribute, together with its concrete type, which is only known at runtime.
+    Dynamic(Type, Box<Value>),
 }
 
 impl ValueKind {
@@ -113,35 +182,46 @@ impl ValueKind {
             ValueKind::Map(_) => "map",
             ValueKind::Tuple(_) => "tuple",
             ValueKind::Object(_) => "object",
+            ValueKind::Dynamic(_, value) => match &**value {
+                Value::Known(known) => known.diagnostic_type_str(),
+                Value::Unknown(_) | Value::Null => "dynamic",
+            },
         }
     }
 }
 
 pub type StringValue = BaseValue<String>;
 pub type I64Value = BaseValue<i64>;
+pub type F64Value = BaseValue<f64>;
+pub type BoolValue = BaseValue<bool>;
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum BaseValue<T> {
-    Unknown,
+    /// A value that will only be known after apply, possibly with refinements about what it will be.
+    Unknown(Refinements),
     Null,
     Known(T),
 }
 
+/// Values default to null, like unset attributes.
+impl<T> Default for BaseValue<T> {
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
 impl<T> BaseValue<T> {
+    /// An unknown value without refinements.
+    pub fn unknown() -> Self {
+        Self::Unknown(Refinements::default())
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, Self::Null)
     }
 
-    fn map<U>(self, f: impl FnOnce(T) -> U) -> BaseValue<U> {
-        self.try_map(|v| Ok(f(v))).unwrap()
-    }
-
-    fn try_map<U>(self, f: impl FnOnce(T) -> DResult<U>) -> DResult<BaseValue<U>> {
-        Ok(match self {
-            Self::Unknown => BaseValue::Unknown,
-            Self::Null => BaseValue::Null,
-            Self::Known(v) => BaseValue::Known(f(v)?),
-        })
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_))
     }
 
     pub fn expect_known(&self, path: AttrPath) -> DResult<&T> {
@@ -149,7 +229,7 @@ impl<T> BaseValue<T> {
             BaseValue::Null => Err(Diagnostic::error_string("expected value, found null value")
                 .with_path(path)
                 .into()),
-            BaseValue::Unknown => Err(Diagnostic::error_string(
+            BaseValue::Unknown(_) => Err(Diagnostic::error_string(
                 "expected known value, found unknown value",
             )
             .with_path(path)
@@ -161,7 +241,7 @@ impl<T> BaseValue<T> {
     pub fn expect_known_or_null(&self, path: AttrPath) -> DResult<Option<&T>> {
         match self {
             BaseValue::Null => Ok(None),
-            BaseValue::Unknown => Err(Diagnostic::error_string(
+            BaseValue::Unknown(_) => Err(Diagnostic::error_string(
                 "expected known value, found unknown value",
             )
             .with_path(path)
@@ -186,6 +266,56 @@ impl<T> From<Option<T>> for BaseValue<T> {
     }
 }
 
+impl Value {
+    /// Whether the value and all values nested inside it are known.
+    pub fn is_fully_known(&self) -> bool {
+        match self {
+            Value::Unknown(_) => false,
+            Value::Null => true,
+            Value::Known(known) => match known {
+                ValueKind::String(_) | ValueKind::Number(_) | ValueKind::Bool(_) => true,
+                ValueKind::List(elems)
+                | ValueKind::Set(ValueSet(elems))
+                | ValueKind::Tuple(elems) => elems.iter().all(Value::is_fully_known),
+                ValueKind::Map(elems) | ValueKind::Object(elems) => {
+                    elems.values().all(Value::is_fully_known)
+                }
+                ValueKind::Dynamic(_, value) => value.is_fully_known(),
+            },
+        }
+    }
+
+    /// Returns the value at `path` relative to this value, or `None` if there is no such value.
+    /// Values inside null values are `None`, values inside unknown values are unknown.
+    pub fn get_path(&self, path: &AttrPath) -> Option<&Value> {
+        path.0.iter().try_fold(self, |value, segment| {
+            let value = value.without_dynamic();
+            let known = match value {
+                Value::Null => return None,
+                Value::Unknown(_) => return Some(value),
+                Value::Known(known) => known,
+            };
+            match (known, segment) {
+                (ValueKind::Object(attrs), AttrPathSegment::AttributeName(name)) => attrs.get(name),
+                (ValueKind::Map(elems), AttrPathSegment::ElementKeyString(key)) => elems.get(key),
+                (
+                    ValueKind::List(elems) | ValueKind::Tuple(elems),
+                    AttrPathSegment::ElementKeyInt(key),
+                ) => usize::try_from(*key).ok().and_then(|key| elems.get(key)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns the value inside dynamic values, or this value if it's not dynamic.
+    pub fn without_dynamic(&self) -> &Value {
+        match self {
+            Value::Known(ValueKind::Dynamic(_, value)) => value.without_dynamic(),
+            value => value,
+        }
+    }
+}
+
 pub trait ValueModel: Sized {
     fn from_value(v: Value, path: &AttrPath) -> DResult<Self>;
 
@@ -196,92 +326,174 @@ pub trait ValueModel: Sized {
     }
 }
 
-impl ValueModel for StringValue {
-    fn from_value(v: Value, path: &AttrPath) -> DResult<Self> {
-        v.try_map(|v| -> DResult<String> {
-            match v {
-                ValueKind::String(s) => Ok(s),
-                _ => Err(Diagnostic::error_string(format!(
-                    "expected string, found {}",
-                    v.diagnostic_type_str()
-                ))
-                .with_path(path.clone())
-                .into()),
-            }
-        })
-    }
+/// A value as it is encoded at a position of some type.
+enum Encoding<'a> {
+    Unknown(&'a Refinements),
+    Null,
+    /// A value at a position of `Type::Dynamic`, which is encoded together with its concrete type.
+    Dynamic(Type, &'a Value),
+    Known(&'a ValueKind, &'a Type),
+}
 
-    fn to_value(self) -> Value {
-        self.map(ValueKind::String)
-    }
+fn encoding_mismatch(known: &ValueKind, typ: &Type) -> Diagnostics {
+    Diagnostic::error_string(format!(
+        "cannot encode {} value as {}",
+        known.diagnostic_type_str(),
+        typ.to_json()
+    ))
+    .into()
 }
 
 // marshal msg pack
 // tftypes/value.go:MarshalMsgPack
 
 impl Value {
-    pub fn msg_pack(&self) -> Vec<u8> {
+    /// Encodes the value as msgpack, the inverse of [`Value::msg_unpack`].
+    /// Values at positions of `Type::Dynamic` are always sent together with their type,
+    /// which is inferred if the value doesn't carry it.
+    pub fn msg_pack(&self, typ: &Type) -> DResult<Vec<u8>> {
         let mut buf = Vec::new();
-        self.msg_pack_inner(&mut buf)
-            .expect("writing to Vec<u8> cannot fail");
-        buf
+        self.msg_pack_inner(&mut buf, typ)?;
+        Ok(buf)
     }
 
-    pub fn msg_pack_inner(&self, wr: &mut Vec<u8>) -> std::io::Result<()> {
+    pub fn msg_pack_inner(&self, wr: &mut Vec<u8>, typ: &Type) -> DResult<()> {
         use rmp::encode as mp;
 
-        let known = match self {
-            Value::Unknown => {
-                wr.extend_from_slice(&[0xd4, 0, 0]);
-                return Ok(());
-            }
-            Value::Null => {
+        let (known, typ) = match self.encoding(typ) {
+            Encoding::Unknown(refinements) => return refinements.msg_pack(wr),
+            Encoding::Null => {
                 mp::write_nil(wr)?;
                 return Ok(());
             }
-            Value::Known(known) => known,
+            Encoding::Dynamic(typ, value) => {
+                mp::write_array_len(wr, 2)?;
+                mp::write_bin(wr, typ.to_json().as_bytes())?;
+                return value.msg_pack_inner(wr, &typ);
+            }
+            Encoding::Known(known, typ) => (known, typ),
         };
 
-        match known {
-            ValueKind::String(s) => {
+        match (known, typ) {
+            (ValueKind::String(s), Type::String) => {
                 mp::write_str(wr, s)?;
             }
-            &ValueKind::Number(n) => {
-                if n.is_infinite() {
-                    if n.signum() == -1.0 {
-                        mp::write_f64(wr, f64::NEG_INFINITY)?;
-                    } else {
-                        mp::write_f64(wr, f64::INFINITY)?;
-                    }
-                } else if (n as i64 as f64) == n {
-                    // is int
-                    mp::write_i64(wr, n as i64)?;
+            // Like terraform-plugin-go, numbers that are neither an integer nor a float are sent as strings.
+            (ValueKind::Number(n), Type::Number) => {
+                if let Some(int) = n.as_i64() {
+                    mp::write_i64(wr, int)?;
+                } else if let Some(uint) = n.as_u64() {
+                    mp::write_u64(wr, uint)?;
+                } else if let Some(float) = n.as_f64_exact() {
+                    mp::write_f64(wr, float)?;
                 } else {
-                    mp::write_f64(wr, n)?;
+                    mp::write_str(wr, &n.to_string())?;
                 }
-                // Terraform handles bigfloats but we do emphatically not care
             }
-            ValueKind::Bool(b) => {
+            (ValueKind::Bool(b), Type::Bool) => {
                 mp::write_bool(wr, *b)?;
             }
-            ValueKind::List(elems) | ValueKind::Set(elems) | ValueKind::Tuple(elems) => {
+            (ValueKind::List(elems), Type::List { elem })
+            | (ValueKind::Set(ValueSet(elems)), Type::Set { elem }) => {
                 mp::write_array_len(wr, elems.len().try_into().unwrap())?;
-                for elem in elems {
-                    elem.msg_pack_inner(wr)?;
+                for value in elems {
+                    value.msg_pack_inner(wr, elem)?;
                 }
             }
-            ValueKind::Map(o) | ValueKind::Object(o) => {
+            (ValueKind::Tuple(elems), Type::Tuple { elems: types })
+                if elems.len() == types.len() =>
+            {
+                mp::write_array_len(wr, elems.len().try_into().unwrap())?;
+                for (value, typ) in elems.iter().zip(types) {
+                    value.msg_pack_inner(wr, typ)?;
+                }
+            }
+            (ValueKind::Map(o), Type::Map { elem }) => {
                 mp::write_map_len(wr, o.len().try_into().unwrap())?;
                 for (key, val) in o {
                     mp::write_str(wr, key)?;
-                    val.msg_pack_inner(wr)?;
+                    val.msg_pack_inner(wr, elem)?;
                 }
             }
+            (ValueKind::Object(o), Type::Object { attrs, .. }) => {
+                mp::write_map_len(wr, o.len().try_into().unwrap())?;
+                for (key, val) in o {
+                    let typ = attrs.get(key).ok_or_else(|| {
+                        Diagnostic::error_string(format!("unexpected attribute '{key}' in object"))
+                    })?;
+                    mp::write_str(wr, key)?;
+                    val.msg_pack_inner(wr, typ)?;
+                }
+            }
+            (known, typ) => return Err(encoding_mismatch(known, typ)),
         }
 
         Ok(())
     }
 
+    /// How the value is encoded at a position of type `typ`, which is shared by the msgpack and JSON encoders.
+    fn encoding<'a>(&'a self, typ: &'a Type) -> Encoding<'a> {
+        // Dynamic values that are already wrapped at a static position are sent as the inner value.
+        let value = self.without_dynamic();
+        if *typ == Type::Dynamic {
+            let typ = match self {
+                Value::Known(ValueKind::Dynamic(typ, _)) if *typ != Type::Dynamic => typ.clone(),
+                _ => value.infer_type(),
+            };
+            // Only null and unknown values of no particular type have no concrete type.
+            if typ != Type::Dynamic {
+                return Encoding::Dynamic(typ, value);
+            }
+        }
+        match value {
+            Value::Unknown(refinements) => Encoding::Unknown(refinements),
+            Value::Null => Encoding::Null,
+            Value::Known(known) => Encoding::Known(known, typ),
+        }
+    }
+
+    /// The type of the value, which is used for values of dynamic attributes that don't carry their type.
+    /// Null and unknown values and the elements of empty collections are of type `Type::Dynamic`.
+    pub fn infer_type(&self) -> Type {
+        fn common<'a>(elems: impl IntoIterator<Item = &'a Value>) -> Box<Type> {
+            let typ = elems
+                .into_iter()
+                .map(Value::infer_type)
+                .find(|typ| *typ != Type::Dynamic);
+            Box::new(typ.unwrap_or(Type::Dynamic))
+        }
+
+        let known = match self {
+            Value::Unknown(_) | Value::Null => return Type::Dynamic,
+            Value::Known(known) => known,
+        };
+        match known {
+            ValueKind::String(_) => Type::String,
+            ValueKind::Number(_) => Type::Number,
+            ValueKind::Bool(_) => Type::Bool,
+            ValueKind::List(elems) => Type::List {
+                elem: common(elems),
+            },
+            ValueKind::Set(elems) => Type::Set {
+                elem: common(elems),
+            },
+            ValueKind::Map(elems) => Type::Map {
+                elem: common(elems.values()),
+            },
+            ValueKind::Tuple(elems) => Type::Tuple {
+                elems: elems.iter().map(Value::infer_type).collect(),
+            },
+            ValueKind::Object(attrs) => Type::Object {
+                attrs: attrs
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.infer_type()))
+                    .collect(),
+                optionals: vec![],
+            },
+            ValueKind::Dynamic(Type::Dynamic, value) => value.infer_type(),
+            ValueKind::Dynamic(typ, _) => typ.clone(),
+        }
+    }
     pub fn msg_unpack(data: &[u8], typ: &Type) -> DResult<Self> {
         tracing::debug!(?typ, ?data, "Unpacking message");
         let mut read = io::Cursor::new(data);
@@ -291,7 +503,7 @@ impl Value {
         })
     }
 
-    fn msg_unpack_inner(rd: &mut io::Cursor<&[u8]>, typ: &Type) -> DResult<Self> {
+    pub(crate) fn msg_unpack_inner(rd: &mut io::Cursor<&[u8]>, typ: &Type) -> DResult<Self> {
         use rmp::decode as mp;
 
         let start = rd.position();
@@ -300,10 +512,18 @@ impl Value {
             return Ok(Value::Null);
         }
         rd.set_position(start);
-        // TODO: Handle unknown values better
         // https://github.com/hashicorp/terraform/blob/main/docs/plugin-protocol/object-wire-format.md#schemaattribute-mapping-rules-for-messagepack
-        if mp::read_fixext1(rd).is_ok() {
-            return Ok(Value::Unknown);
+        if let Ok(meta) = mp::read_ext_meta(rd) {
+            let start = rd.position() as usize;
+            let payload = rd
+                .get_ref()
+                .get(start..start + meta.size as usize)
+                .ok_or_else(|| Diagnostic::error_string("truncated unknown value"))?;
+            rd.set_position((start + payload.len()) as u64);
+            return Ok(Value::Unknown(Refinements::msg_unpack(
+                meta.typeid,
+                payload,
+            )?));
         }
         rd.set_position(start);
 
@@ -320,22 +540,52 @@ impl Value {
                 ValueKind::Bool(b)
             }
             Type::Number => {
+                let marker = mp::read_marker(rd).map_err(mp::ValueReadError::from)?;
+                rd.set_position(start);
+                let number = match marker {
+                    rmp::Marker::F32 => Number::from(f64::from(mp::read_f32(rd)?)),
+                    rmp::Marker::F64 => Number::from(mp::read_f64(rd)?),
+                    rmp::Marker::U64 => Number::from(mp::read_int::<u64, _>(rd)?),
+                    // Numbers that don't fit into an integer or float are sent as decimal strings.
+                    rmp::Marker::FixStr(_)
+                    | rmp::Marker::Str8
+                    | rmp::Marker::Str16
+                    | rmp::Marker::Str32 => {
+                        let s = read_string(rd)?;
+                        Number::parse(&s).ok_or_else(|| {
+                            Diagnostic::error_string(format!("invalid number: '{s}'"))
+                        })?
+                    }
+                    _ => Number::from(mp::read_int::<i64, _>(rd)?),
+                };
+                ValueKind::Number(number)
+            }
+            Type::String => ValueKind::String(read_string(rd)?),
+            // Dynamic values are wrapped in `[type, value]`, where `type` is the JSON type as bytes.
+            Type::Dynamic => {
+                let len = mp::read_array_len(rd)?;
+                if len != 2 {
+                    return Err(Diagnostic::error_string(format!(
+                        "expected 2 elems, found {len} elems in dynamic value"
+                    ))
+                    .into());
+                }
+
                 let prev = rd.position();
-                if let Ok(int) = mp::read_int::<i64, _>(rd) {
-                    ValueKind::Number(int as f64)
-                } else {
-                    rd.set_position(prev);
-                    if let Ok(f32) = mp::read_f32(rd) {
-                        ValueKind::Number(f32 as f64)
-                    } else {
+                let typ_len = match mp::read_bin_len(rd) {
+                    Ok(len) => len,
+                    Err(_) => {
                         rd.set_position(prev);
-                        let f64 = mp::read_f64(rd)?;
-                        ValueKind::Number(f64)
+                        mp::read_str_len(rd)?
                     }
-                }
+                };
+                let mut typ_json = vec![0; std::cmp::min(typ_len, 1024 * 1024) as usize];
+                rd.read_exact(&mut typ_json)?;
+                let typ = Type::from_json(&typ_json)?;
+
+                let value = Value::msg_unpack_inner(rd, &typ)?;
+                ValueKind::Dynamic(typ, Box::new(value))
             }
-            Type::String => ValueKind::String(read_string(rd)?),
-            Type::Dynamic => todo!("dynamic"),
             Type::List { elem } => {
                 let len = mp::read_array_len(rd)?;
 
@@ -362,7 +612,7 @@ impl Value {
                 let elems = (0..len)
                     .map(|_| Value::msg_unpack_inner(rd, elem))
                     .collect::<Result<Vec<_>, _>>()?;
-                ValueKind::Set(elems)
+                ValueKind::Set(ValueSet::from_unique(elems, elem)?)
             }
             Type::Object { attrs, optionals } => {
                 let len = mp::read_map_len(rd)?;
@@ -419,11 +669,286 @@ impl Value {
     }
 }
 
+// unmarshal JSON
+// tftypes/value_json.go:jsonUnmarshal
+
+impl Value {
+    pub fn json_unpack(data: &[u8], typ: &Type) -> DResult<Self> {
+        let json = serde_json::from_slice::<&RawValue>(data)?;
+        Self::json_unpack_raw(json, typ).map_err(|mut diag| {
+            diag.diags[0].msg = format!("JSON decoding error: {}", diag.diags[0].msg);
+            diag
+        })
+    }
+
+    /// Decodes an already parsed JSON value.
+    /// `serde_json::Value` only keeps numbers as precise as an `f64`, so prefer [`Value::json_unpack`] where possible.
+    pub fn json_unpack_value(json: &serde_json::Value, typ: &Type) -> DResult<Self> {
+        Self::json_unpack_raw(&serde_json::value::to_raw_value(json)?, typ)
+    }
+
+    /// Like for msgpack, null is a null value for every type, including dynamic.
+    /// JSON cannot represent unknown values, so this will never return any.
+    /// This works on the raw JSON text to keep all digits of numbers.
+    fn json_unpack_raw(json: &RawValue, typ: &Type) -> DResult<Self> {
+        let text = json.get().trim();
+        let mismatch = |expected: &str| -> DResult<Self> {
+            Err(Diagnostic::error_string(format!("expected {expected}, found {text}")).into())
+        };
+        let array = || serde_json::from_str::<Vec<&RawValue>>(text).ok();
+        let object = || serde_json::from_str::<BTreeMap<String, &RawValue>>(text).ok();
+
+        if text == "null" {
+            return Ok(Value::Null);
+        }
+        let value = match typ {
+            Type::Bool => match serde_json::from_str(text) {
+                Ok(b) => ValueKind::Bool(b),
+                Err(_) => return mismatch("bool"),
+            },
+            Type::Number => {
+                // Like tftypes, accept numbers as strings to allow for arbitrary precision.
+                let n = match serde_json::from_str::<String>(text) {
+                    Ok(s) => Number::parse(&s),
+                    Err(_) => Number::parse(text),
+                };
+                match n {
+                    Some(n) => ValueKind::Number(n),
+                    None => return mismatch("number"),
+                }
+            }
+            Type::String => match serde_json::from_str(text) {
+                Ok(s) => ValueKind::String(s),
+                Err(_) => return mismatch("string"),
+            },
+            // Dynamic values are wrapped in `{"type": type, "value": value}`.
+            Type::Dynamic => {
+                let Some(wrapper) = object() else {
+                    return mismatch("object with 'type' and 'value'");
+                };
+                let (Some(typ), Some(value), 2) =
+                    (wrapper.get("type"), wrapper.get("value"), wrapper.len())
+                else {
+                    return mismatch("object with 'type' and 'value'");
+                };
+                let typ = Type::from_json(typ.get().as_bytes())?;
+                let value = Value::json_unpack_raw(value, &typ)?;
+                ValueKind::Dynamic(typ, Box::new(value))
+            }
+            Type::List { elem } => {
+                let Some(elems) = array() else {
+                    return mismatch("list");
+                };
+                ValueKind::List(
+                    elems
+                        .into_iter()
+                        .map(|v| Value::json_unpack_raw(v, elem))
+                        .collect::<DResult<Vec<_>>>()?,
+                )
+            }
+            Type::Set { elem } => {
+                let Some(elems) = array() else {
+                    return mismatch("set");
+                };
+                ValueKind::Set(ValueSet::from_unique(
+                    elems
+                        .into_iter()
+                        .map(|v| Value::json_unpack_raw(v, elem))
+                        .collect::<DResult<Vec<_>>>()?,
+                    elem,
+                )?)
+            }
+            Type::Map { elem } => {
+                let Some(elems) = object() else {
+                    return mismatch("map");
+                };
+                ValueKind::Map(
+                    elems
+                        .into_iter()
+                        .map(|(k, v)| Ok((k, Value::json_unpack_raw(v, elem)?)))
+                        .collect::<DResult<BTreeMap<_, _>>>()?,
+                )
+            }
+            Type::Object { attrs, .. } => {
+                let Some(elems) = object() else {
+                    return mismatch("object");
+                };
+                if let Some(key) = elems.keys().find(|key| !attrs.contains_key(*key)) {
+                    return Err(
+                        Diagnostic::error_string(format!("unexpected attribute: '{key}'")).into(),
+                    );
+                }
+
+                // Like Terraform, we treat absent attributes as null.
+                ValueKind::Object(
+                    attrs
+                        .iter()
+                        .map(|(k, typ)| {
+                            let value = match elems.get(k) {
+                                Some(v) => Value::json_unpack_raw(v, typ)?,
+                                None => Value::Null,
+                            };
+                            Ok((k.clone(), value))
+                        })
+                        .collect::<DResult<BTreeMap<_, _>>>()?,
+                )
+            }
+            Type::Tuple { elems: typs } => {
+                let Some(elems) = array() else {
+                    return mismatch("tuple");
+                };
+                if typs.len() != elems.len() {
+                    return Err(Diagnostic::error_string(format!(
+                        "expected {} elems, found {} elems in tuple",
+                        typs.len(),
+                        elems.len()
+                    ))
+                    .into());
+                }
+                ValueKind::Tuple(
+                    elems
+                        .into_iter()
+                        .zip(typs)
+                        .map(|(v, typ)| Value::json_unpack_raw(v, typ))
+                        .collect::<DResult<Vec<_>>>()?,
+                )
+            }
+        };
+
+        Ok(Value::Known(value))
+    }
+}
+
+// marshal JSON
+
+impl Value {
+    /// Encodes the value as JSON, the inverse of [`Value::json_unpack`].
+    /// Null values and values at positions of `Type::Dynamic` are encoded like for msgpack.
+    /// JSON cannot represent unknown values, so these are an error.
+    pub fn json_pack(&self, typ: &Type) -> DResult<Vec<u8>> {
+        let mut wr = String::new();
+        self.json_pack_inner(&mut wr, typ)?;
+        Ok(wr.into_bytes())
+    }
+
+    /// Writes the JSON text directly, as `serde_json::Value` can't hold numbers of arbitrary precision.
+    fn json_pack_inner(&self, wr: &mut String, typ: &Type) -> DResult<()> {
+        let (known, typ) = match self.encoding(typ) {
+            Encoding::Unknown(_) => {
+                return Err(Diagnostic::error_string(
+                    "unknown values cannot be represented in JSON",
+                )
+                .into())
+            }
+            Encoding::Null => {
+                wr.push_str("null");
+                return Ok(());
+            }
+            Encoding::Dynamic(typ, value) => {
+                wr.push_str(r#"{"type":"#);
+                wr.push_str(&typ.to_json());
+                wr.push_str(r#","value":"#);
+                value.json_pack_inner(wr, &typ)?;
+                wr.push('}');
+                return Ok(());
+            }
+            Encoding::Known(known, typ) => (known, typ),
+        };
+
+        match (known, typ) {
+            (ValueKind::String(s), Type::String) => wr.push_str(&serde_json::to_string(s)?),
+            (ValueKind::Number(n), Type::Number) => json_pack_number(wr, n)?,
+            (ValueKind::Bool(b), Type::Bool) => wr.push_str(if *b { "true" } else { "false" }),
+            (ValueKind::List(elems), Type::List { elem })
+            | (ValueKind::Set(ValueSet(elems)), Type::Set { elem }) => {
+                json_pack_array(wr, elems.iter().map(|v| (v, &**elem)))?
+            }
+            (ValueKind::Tuple(elems), Type::Tuple { elems: types })
+                if elems.len() == types.len() =>
+            {
+                json_pack_array(wr, elems.iter().zip(types))?
+            }
+            (ValueKind::Map(o), Type::Map { elem }) => {
+                json_pack_object(wr, o.iter().map(|(k, v)| Ok((k, v, &**elem))))?
+            }
+            (ValueKind::Object(o), Type::Object { attrs, .. }) => json_pack_object(
+                wr,
+                o.iter().map(|(k, v)| {
+                    let typ = attrs.get(k).ok_or_else(|| {
+                        Diagnostic::error_string(format!("unexpected attribute '{k}' in object"))
+                    })?;
+                    Ok((k, v, typ))
+                }),
+            )?,
+            (known, typ) => return Err(encoding_mismatch(known, typ)),
+        }
+        Ok(())
+    }
+}
+
+fn json_pack_number(wr: &mut String, n: &Number) -> DResult<()> {
+    if let Some(int) = n.as_i64() {
+        wr.push_str(&int.to_string());
+    } else if let Some(uint) = n.as_u64() {
+        wr.push_str(&uint.to_string());
+    } else if let Some(float) = n.as_f64_exact() {
+        if !float.is_finite() {
+            return Err(Diagnostic::error_string(format!(
+                "the number {n} cannot be represented in JSON"
+            ))
+            .into());
+        }
+        wr.push_str(&serde_json::to_string(&float)?);
+    } else {
+        // Like Terraform, numbers of arbitrary precision are written as number literals.
+        let literal = n.to_string();
+        if serde_json::from_str::<&RawValue>(&literal).is_ok() {
+            wr.push_str(&literal);
+        } else {
+            wr.push_str(&serde_json::to_string(&literal)?);
+        }
+    }
+    Ok(())
+}
+
+fn json_pack_array<'a>(
+    wr: &mut String,
+    elems: impl Iterator<Item = (&'a Value, &'a Type)>,
+) -> DResult<()> {
+    wr.push('[');
+    for (i, (elem, typ)) in elems.enumerate() {
+        if i > 0 {
+            wr.push(',');
+        }
+        elem.json_pack_inner(wr, typ)?;
+    }
+    wr.push(']');
+    Ok(())
+}
+
+fn json_pack_object<'a>(
+    wr: &mut String,
+    elems: impl Iterator<Item = DResult<(&'a String, &'a Value, &'a Type)>>,
+) -> DResult<()> {
+    wr.push('{');
+    for (i, elem) in elems.enumerate() {
+        let (key, elem, typ) = elem?;
+        if i > 0 {
+            wr.push(',');
+        }
+        wr.push_str(&serde_json::to_string(key)?);
+        wr.push(':');
+        elem.json_pack_inner(wr, typ)?;
+    }
+    wr.push('}');
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::BTreeMap;
 
-    use crate::{Type, Value, ValueKind};
+    use crate::{Number, Refinements, Type, Value, ValueKind, ValueSet};
 
     #[test]
     fn type_json() {
@@ -477,7 +1002,195 @@ mod test {
         for (typ, expected) in typs {
             let actual_str = typ.to_json();
             assert_eq!(actual_str, expected);
+            assert_eq!(Type::from_json(expected.as_bytes()).unwrap(), typ);
         }
+
+        assert!(Type::from_json(br#""strin""#).is_err());
+        assert!(Type::from_json(br#"["list"]"#).is_err());
+        assert!(Type::from_json(br#"["object",{},[1]]"#).is_err());
+    }
+
+    #[test]
+    fn roundtrip_numbers() {
+        let typ = Type::List {
+            elem: Box::new(Type::Number),
+        };
+        let numbers = [
+            Number::from(1229133604588470373_i64),
+            Number::from(u64::MAX),
+            Number::from(1_u64 << 63),
+            Number::from(-0.25),
+            Number::parse("123456789012345678901234567890.5").unwrap(),
+        ];
+        let value = Value::Known(ValueKind::List(
+            numbers
+                .iter()
+                .map(|n| Value::Known(ValueKind::Number(n.clone())))
+                .collect(),
+        ));
+
+        assert_eq!(
+            Value::msg_unpack(&value.msg_pack(&typ).unwrap(), &typ).unwrap(),
+            value
+        );
+        assert_eq!(
+            Value::json_unpack(&value.json_pack(&typ).unwrap(), &typ).unwrap(),
+            value
+        );
+
+        // Terraform writes numbers of arbitrary precision as number literals.
+        let json = br#"[123456789012345678901234567890.5,1e400]"#;
+        let big = Value::Known(ValueKind::List(vec![
+            Value::Known(ValueKind::Number(
+                Number::parse("123456789012345678901234567890.5").unwrap(),
+            )),
+            Value::Known(ValueKind::Number(Number::parse("1e400").unwrap())),
+        ]));
+        assert_eq!(Value::json_unpack(json, &typ).unwrap(), big);
+        assert_eq!(big.json_pack(&typ).unwrap(), json);
+    }
+
+    #[test]
+    fn roundtrip_refined_unknown() {
+        let typ = Type::List {
+            elem: Box::new(Type::Object {
+                attrs: BTreeMap::from([
+                    ("id".into(), Type::String),
+                    ("size".into(), Type::Number),
+                    (
+                        "tags".into(),
+                        Type::List {
+                            elem: Box::new(Type::String),
+                        },
+                    ),
+                ]),
+                optionals: vec![],
+            }),
+        };
+        let value = Value::Known(ValueKind::List(vec![
+            Value::unknown(),
+            Value::Known(ValueKind::Object(BTreeMap::from([
+                (
+                    "id".into(),
+                    Value::Unknown(Refinements {
+                        string_prefix: Some("cat-".into()),
+                        ..Refinements::not_null()
+                    }),
+                ),
+                (
+                    "size".into(),
+                    Value::Unknown(Refinements {
+                        number_lower_bound: Some((0.into(), true)),
+                        number_upper_bound: Some((Number::from(9.5), false)),
+                        ..Refinements::default()
+                    }),
+                ),
+                (
+                    "tags".into(),
+                    Value::Unknown(Refinements {
+                        length_lower_bound: Some(1),
+                        length_upper_bound: Some(3),
+                        ..Refinements::default()
+                    }),
+                ),
+            ]))),
+        ]));
+
+        let data = value.msg_pack(&typ).unwrap();
+        assert_eq!(&data[1..4], [0xd4, 0, 0]);
+        assert_eq!(Value::msg_unpack(&data, &typ).unwrap(), value);
+
+        // Key 1 is "is null", so not null is `false`.
+        assert_eq!(
+            Value::Unknown(Refinements::not_null())
+                .msg_pack(&Type::String)
+                .unwrap(),
+            [0xc7, 3, 12, 0x81, 1, 0xc2]
+        );
+
+        // Unknown refinements are ignored.
+        let data = [0xc7, 5, 12, 0x82, 1, 0xc2, 9, 0xc3];
+        assert_eq!(
+            Value::msg_unpack(&data, &Type::String).unwrap(),
+            Value::Unknown(Refinements::not_null())
+        );
+    }
+
+    #[test]
+    fn roundtrip_dynamic() {
+        let typ = Type::Object {
+            attrs: BTreeMap::from([
+                ("id".into(), Type::String),
+                ("payload".into(), Type::Dynamic),
+            ]),
+            optionals: vec![],
+        };
+        let payload_typ = Type::Tuple {
+            elems: vec![Type::String, Type::Number],
+        };
+        let value = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("id".into(), Value::Known(ValueKind::String("meow".into()))),
+            (
+                "payload".into(),
+                Value::Known(ValueKind::Dynamic(
+                    payload_typ.clone(),
+                    Box::new(Value::Known(ValueKind::Tuple(vec![
+                        Value::Known(ValueKind::String("a".into())),
+                        Value::Known(ValueKind::Number(1.into())),
+                    ]))),
+                )),
+            ),
+        ])));
+
+        let data = value.msg_pack(&typ).unwrap();
+        assert_eq!(Value::msg_unpack(&data, &typ).unwrap(), value);
+
+        let json =
+            br#"{"id":"meow","payload":{"type":["tuple",["string","number"]],"value":["a",1]}}"#;
+        assert_eq!(Value::json_unpack(json, &typ).unwrap(), value);
+        assert_eq!(
+            Value::json_unpack(&value.json_pack(&typ).unwrap(), &typ).unwrap(),
+            value
+        );
+        assert!(Value::unknown().json_pack(&typ).is_err());
+
+        let path = crate::AttrPath::attr("payload").append_element_key_int(0);
+        assert_eq!(
+            value.get_path(&path),
+            Some(&Value::Known(ValueKind::String("a".into())))
+        );
+
+        // Values that don't carry their type are sent with the inferred type.
+        let plain = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("id".into(), Value::Known(ValueKind::String("meow".into()))),
+            (
+                "payload".into(),
+                Value::Known(ValueKind::String("a".into())),
+            ),
+        ])));
+        let decoded = Value::msg_unpack(&plain.msg_pack(&typ).unwrap(), &typ).unwrap();
+        assert_eq!(
+            decoded.get_path(&crate::AttrPath::attr("payload")),
+            Some(&Value::Known(ValueKind::Dynamic(
+                Type::String,
+                Box::new(Value::Known(ValueKind::String("a".into())))
+            )))
+        );
+        assert!(plain.msg_pack(&Type::String).is_err());
+        assert_eq!(
+            plain.json_pack(&typ).unwrap(),
+            br#"{"id":"meow","payload":{"type":"string","value":"a"}}"#
+        );
+        assert!(plain.json_pack(&Type::String).is_err());
+
+        let null = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("id".into(), Value::Null),
+            ("payload".into(), Value::Null),
+        ])));
+        assert_eq!(
+            Value::msg_unpack(&null.msg_pack(&typ).unwrap(), &typ).unwrap(),
+            null
+        );
     }
 
     #[test]
@@ -507,7 +1220,7 @@ mod test {
                     Value::Known(ValueKind::String("???".into()))
                 ),
                 ("discord_id".into(), Value::Null),
-                ("id".into(), Value::Unknown),
+                ("id".into(), Value::unknown()),
                 (
                     "name".into(),
                     Value::Known(ValueKind::String("meow".into()))
@@ -515,4 +1228,121 @@ mod test {
             ])))
         );
     }
+
+    #[test]
+    fn roundtrip_collections() {
+        let typ = Type::Object {
+            attrs: BTreeMap::from([
+                ("enabled".into(), Type::Bool),
+                ("ratio".into(), Type::Number),
+                (
+                    "ids".into(),
+                    Type::List {
+                        elem: Box::new(Type::Number),
+                    },
+                ),
+                (
+                    "names".into(),
+                    Type::Set {
+                        elem: Box::new(Type::String),
+                    },
+                ),
+                (
+                    "tags".into(),
+                    Type::Map {
+                        elem: Box::new(Type::String),
+                    },
+                ),
+            ]),
+            optionals: vec![],
+        };
+        let value = Value::Known(ValueKind::Object(BTreeMap::from([
+            ("enabled".into(), Value::Known(ValueKind::Bool(true))),
+            ("ratio".into(), Value::Known(ValueKind::Number(0.5.into()))),
+            (
+                "ids".into(),
+                Value::Known(ValueKind::List(vec![
+                    Value::Known(ValueKind::Number(1.into())),
+                    Value::unknown(),
+                ])),
+            ),
+            (
+                "names".into(),
+                Value::Known(ValueKind::Set(ValueSet::from(vec![Value::Known(
+                    ValueKind::String("meow".into()),
+                )]))),
+            ),
+            (
+                "tags".into(),
+                Value::Known(ValueKind::Map(BTreeMap::from([(
+                    "owner".into(),
+                    Value::Null,
+                )]))),
+            ),
+        ])));
+
+        let data = value.msg_pack(&typ).unwrap();
+        assert_eq!(Value::msg_unpack(&data, &typ).unwrap(), value);
+    }
+
+    #[test]
+    fn decode_set() {
+        let typ = Type::Set {
+            elem: Box::new(Type::String),
+        };
+        let set = |elems: &[&str]| {
+            Value::Known(ValueKind::Set(
+                elems
+                    .iter()
+                    .map(|s| Value::Known(ValueKind::String((*s).to_owned())))
+                    .collect(),
+            ))
+        };
+
+        let data = set(&["b", "a"]).msg_pack(&typ).unwrap();
+        assert_eq!(Value::msg_unpack(&data, &typ).unwrap(), set(&["a", "b"]));
+        assert_ne!(Value::msg_unpack(&data, &typ).unwrap(), set(&["a"]));
+
+        // `["a", "a"]`, which can't be constructed as a set.
+        let data = [0x92, 0xa1, b'a', 0xa1, b'a'];
+        assert!(Value::msg_unpack(&data, &typ).is_err());
+        assert!(Value::json_unpack(br#"["a","a"]"#, &typ).is_err());
+    }
+
+    #[test]
+    fn decode_json_object() {
+        let typ = Type::Object {
+            attrs: BTreeMap::from([
+                ("id".into(), Type::String),
+                ("port".into(), Type::Number),
+                (
+                    "tags".into(),
+                    Type::List {
+                        elem: Box::new(Type::String),
+                    },
+                ),
+            ]),
+            optionals: vec![],
+        };
+
+        let value = Value::json_unpack(br#"{"id":"meow","tags":["a",null]}"#, &typ);
+
+        assert_eq!(
+            value.unwrap(),
+            Value::Known(ValueKind::Object(BTreeMap::from([
+                ("id".into(), Value::Known(ValueKind::String("meow".into()))),
+                ("port".into(), Value::Null),
+                (
+                    "tags".into(),
+                    Value::Known(ValueKind::List(vec![
+                        Value::Known(ValueKind::String("a".into())),
+                        Value::Null
+                    ]))
+                ),
+            ])))
+        );
+
+        assert!(Value::json_unpack(br#"{"id":"meow","uwu":1}"#, &typ).is_err());
+        assert!(Value::json_unpack(br#"{"id":1}"#, &typ).is_err());
+    }
 }